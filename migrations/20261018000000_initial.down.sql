@@ -0,0 +1,12 @@
+DROP TABLE IF EXISTS wallets;
+DROP TABLE IF EXISTS job_runs;
+DROP TABLE IF EXISTS prices;
+DROP TABLE IF EXISTS miner_predictions;
+DROP TABLE IF EXISTS outcomes;
+DROP TABLE IF EXISTS predictions;
+DROP TABLE IF EXISTS market_outcomes;
+DROP TABLE IF EXISTS market_changes;
+DROP TABLE IF EXISTS markets;
+DROP TABLE IF EXISTS events;
+DROP TABLE IF EXISTS oauth_states;
+DROP TABLE IF EXISTS accounts;
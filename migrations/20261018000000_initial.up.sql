@@ -0,0 +1,176 @@
+-- Baseline schema. Every statement is idempotent so databases created from the old schema.sql can adopt
+-- migrations without being recreated; the legacy conversions and backfills below are no-ops on fresh databases.
+SET timezone TO 'UTC';
+
+CREATE TABLE IF NOT EXISTS accounts (
+    account_id VARCHAR(255) PRIMARY KEY,
+    api_key VARCHAR(255) NOT NULL UNIQUE,
+    google_id VARCHAR(255) NOT NULL UNIQUE,
+    session_id VARCHAR(255) DEFAULT NULL UNIQUE,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS oauth_states (
+    session_id VARCHAR(255) PRIMARY KEY,
+    pkce_challenge VARCHAR(255) NOT NULL,
+    csrf_token VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL DEFAULT NOW() + INTERVAL '10 minutes'
+);
+
+CREATE TABLE IF NOT EXISTS events (
+    event_id VARCHAR(255) PRIMARY KEY,
+    slug VARCHAR(255) DEFAULT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    end_date TIMESTAMP DEFAULT NULL,
+    neg_risk BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS markets (
+    condition_id VARCHAR(255) PRIMARY KEY,
+    question TEXT NOT NULL,
+    description TEXT NOT NULL,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    yes_token_id VARCHAR(255) DEFAULT NULL,
+    no_token_id VARCHAR(255) DEFAULT NULL,
+    end_date TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved BOOLEAN NOT NULL DEFAULT FALSE,
+    outcome VARCHAR(255) DEFAULT NULL,
+    winning_token_id VARCHAR(255) DEFAULT NULL,
+    final_price FLOAT DEFAULT NULL,
+    resolved_at TIMESTAMP DEFAULT NULL,
+    event_id VARCHAR(255) DEFAULT NULL
+);
+
+CREATE INDEX IF NOT EXISTS markets_event_id_idx ON markets (event_id);
+
+-- Convert tags stored as "[Crypto, AI]" strings before tags became an array
+DO $$
+BEGIN
+    IF (SELECT data_type FROM information_schema.columns WHERE table_name = 'markets' AND column_name = 'tags') = 'text' THEN
+        ALTER TABLE markets ALTER COLUMN tags TYPE TEXT[] USING string_to_array(trim(both '[]' from tags), ', ');
+        ALTER TABLE markets ALTER COLUMN tags SET DEFAULT '{}';
+    END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS markets_tags_idx ON markets USING GIN (tags);
+CREATE INDEX IF NOT EXISTS markets_search_idx ON markets USING GIN (to_tsvector('english', question || ' ' || description));
+CREATE INDEX IF NOT EXISTS markets_end_date_idx ON markets (end_date, condition_id);
+
+CREATE TABLE IF NOT EXISTS market_changes (
+    change_id BIGSERIAL PRIMARY KEY,
+    condition_id VARCHAR(255) NOT NULL,
+    field VARCHAR(64) NOT NULL,
+    old_value TEXT DEFAULT NULL,
+    new_value TEXT DEFAULT NULL,
+    changed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS market_changes_condition_id_idx ON market_changes (condition_id, changed_at);
+
+CREATE TABLE IF NOT EXISTS market_outcomes (
+    token_id VARCHAR(255) PRIMARY KEY,
+    condition_id VARCHAR(255) NOT NULL,
+    outcome VARCHAR(255) NOT NULL,
+    position INT NOT NULL,
+    winner BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS market_outcomes_condition_id_idx ON market_outcomes (condition_id, position);
+
+-- Backfill outcomes for binary markets ingested before market_outcomes existed
+INSERT INTO market_outcomes (token_id, condition_id, outcome, position, winner)
+SELECT t.token_id, m.condition_id, t.outcome, t.position, COALESCE(m.winning_token_id = t.token_id, FALSE)
+FROM markets m
+CROSS JOIN LATERAL (VALUES (m.yes_token_id, 'Yes', 0), (m.no_token_id, 'No', 1)) AS t(token_id, outcome, position)
+WHERE t.token_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS predictions (
+    prediction_id VARCHAR(255) PRIMARY KEY,
+    condition_id VARCHAR(255) NOT NULL,
+    token_id VARCHAR(255) DEFAULT NULL,
+    end_date TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Predictions forecast a single outcome token; binary markets predated this and forecast the YES token
+ALTER TABLE predictions ADD COLUMN IF NOT EXISTS token_id VARCHAR(255) DEFAULT NULL;
+ALTER TABLE predictions DROP CONSTRAINT IF EXISTS predictions_condition_id_key;
+
+UPDATE predictions p
+SET token_id = m.yes_token_id
+FROM markets m
+WHERE m.condition_id = p.condition_id
+AND p.token_id IS NULL
+AND m.yes_token_id IS NOT NULL;
+
+-- One prediction per outcome token, and at most one legacy prediction without a token per market
+CREATE UNIQUE INDEX IF NOT EXISTS predictions_condition_id_token_id_key ON predictions (condition_id, token_id) WHERE token_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS predictions_condition_id_untokened_key ON predictions (condition_id) WHERE token_id IS NULL;
+
+CREATE TABLE IF NOT EXISTS outcomes (
+    prediction_id VARCHAR(255) NOT NULL,
+    weighted FLOAT NOT NULL,
+    community FLOAT NOT NULL,
+    aggregator VARCHAR(64) NOT NULL DEFAULT 'mean',
+    raw JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (prediction_id, created_at)
+);
+
+CREATE TABLE IF NOT EXISTS miner_predictions (
+    prediction_id VARCHAR(255) NOT NULL,
+    miner_id VARCHAR(255) NOT NULL,
+    predicted_outcome FLOAT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (prediction_id, miner_id, created_at)
+);
+
+CREATE INDEX IF NOT EXISTS miner_predictions_miner_id_idx ON miner_predictions (miner_id, created_at);
+
+-- Backfill miner forecasts from outcomes stored before miner_predictions existed
+INSERT INTO miner_predictions (prediction_id, miner_id, predicted_outcome, created_at)
+SELECT o.prediction_id, COALESCE(e->>'minerHotkey', e->>'minerUid'), (e->>'predictedOutcome')::float8, o.created_at
+FROM outcomes o
+CROSS JOIN LATERAL jsonb_array_elements(o.raw->'predictions') e
+WHERE COALESCE(e->>'minerHotkey', e->>'minerUid') IS NOT NULL
+AND e->>'predictedOutcome' ~ '^[0-9]*\.?[0-9]+$'
+ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS prices (
+    condition_id VARCHAR(255) NOT NULL,
+    token_id VARCHAR(255) NOT NULL,
+    price FLOAT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (token_id, created_at)
+);
+
+-- Scheduler run history; replaces the kv row that served as a task lock
+CREATE TABLE IF NOT EXISTS job_runs (
+    id BIGSERIAL PRIMARY KEY,
+    job VARCHAR(64) NOT NULL,
+    triggered_by VARCHAR(16) NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'running',
+    counters JSONB DEFAULT NULL,
+    error TEXT DEFAULT NULL,
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP DEFAULT NULL
+);
+
+CREATE INDEX IF NOT EXISTS job_runs_job_idx ON job_runs (job, started_at DESC);
+
+CREATE TABLE IF NOT EXISTS wallets (
+    account_id VARCHAR(255) PRIMARY KEY,
+    subscription_id VARCHAR(255) NOT NULL UNIQUE,
+    address VARCHAR(255) NOT NULL UNIQUE,
+    secret VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
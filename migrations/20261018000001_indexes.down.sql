@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS oauth_states_expires_at_idx;
+DROP INDEX IF EXISTS miner_predictions_created_at_idx;
+DROP INDEX IF EXISTS markets_unresolved_idx;
+DROP INDEX IF EXISTS prices_condition_id_idx;
+DROP INDEX IF EXISTS outcomes_created_at_idx;
+DROP INDEX IF EXISTS predictions_end_date_idx;
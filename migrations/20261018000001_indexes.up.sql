@@ -0,0 +1,7 @@
+-- Lookups that previously fell back to sequential scans
+CREATE INDEX IF NOT EXISTS predictions_end_date_idx ON predictions (end_date);
+CREATE INDEX IF NOT EXISTS outcomes_created_at_idx ON outcomes (created_at);
+CREATE INDEX IF NOT EXISTS prices_condition_id_idx ON prices (condition_id, created_at);
+CREATE INDEX IF NOT EXISTS markets_unresolved_idx ON markets (end_date) WHERE NOT resolved;
+CREATE INDEX IF NOT EXISTS miner_predictions_created_at_idx ON miner_predictions (created_at);
+CREATE INDEX IF NOT EXISTS oauth_states_expires_at_idx ON oauth_states (expires_at);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS kv (
+    kv_key VARCHAR(255) PRIMARY KEY,
+    kv_value VARCHAR(255) DEFAULT NULL,
+    kv_ts TIMESTAMP DEFAULT NULL
+);
@@ -0,0 +1,4 @@
+-- The kv row the old task loop used as a lock, superseded by job_runs and advisory locks. Separate from the
+-- initial migration so it can be reverted on its own, and so the initial migration is safe to apply while
+-- replicas still running the old loop are being replaced.
+DROP TABLE IF EXISTS kv;
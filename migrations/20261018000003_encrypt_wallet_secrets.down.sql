@@ -0,0 +1,12 @@
+-- Encrypted secrets cannot be restored to plaintext here, so refuse rather than leave them unreadable.
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM wallets WHERE key_version IS NOT NULL) THEN
+        RAISE EXCEPTION 'wallets contain encrypted secrets';
+    END IF;
+END
+$$;
+
+ALTER TABLE wallets DROP COLUMN IF EXISTS key_version;
+ALTER TABLE wallets DROP COLUMN IF EXISTS secret_key;
+ALTER TABLE wallets ALTER COLUMN secret TYPE VARCHAR(255);
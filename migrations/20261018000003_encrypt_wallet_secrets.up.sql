@@ -0,0 +1,5 @@
+-- Wallet secrets are sealed under a per-record data key; `secret_key` holds that data key wrapped by master key
+-- `key_version`. Rows with no key version still hold a plaintext secret until `keys rotate` seals them.
+ALTER TABLE wallets ALTER COLUMN secret TYPE TEXT;
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS secret_key TEXT;
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS key_version INTEGER;
@@ -0,0 +1,9 @@
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM wallets WHERE secret IS NULL) THEN
+        RAISE EXCEPTION 'wallets have keys held outside the database';
+    END IF;
+END
+$$;
+
+ALTER TABLE wallets ALTER COLUMN secret SET NOT NULL;
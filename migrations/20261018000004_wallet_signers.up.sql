@@ -0,0 +1,2 @@
+-- Wallets whose key is held by the keystore or signer process have no secret in their row.
+ALTER TABLE wallets ALTER COLUMN secret DROP NOT NULL;
@@ -0,0 +1,33 @@
+-- Double-entry ledger. Each transaction is one money movement for an account (a deposit, withdrawal or swap); its
+-- entries move amounts between ledger accounts (`wallet:<account_id>`, `external`, `gas`, `network_fees`,
+-- `swap_fees`) and sum to zero per asset.
+CREATE TABLE IF NOT EXISTS ledger_transactions (
+    id BIGSERIAL PRIMARY KEY,
+    account_id VARCHAR(255) NOT NULL,
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('deposit', 'withdrawal', 'swap')),
+    status VARCHAR(16) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'confirmed', 'failed')),
+    signature VARCHAR(128) DEFAULT NULL,
+    external_id VARCHAR(255) DEFAULT NULL,
+    error TEXT DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- Webhook deliveries are retried, so the same on-chain transaction must only be recorded once per account and kind.
+CREATE UNIQUE INDEX IF NOT EXISTS ledger_transactions_signature_idx ON ledger_transactions (account_id, kind, signature);
+CREATE INDEX IF NOT EXISTS ledger_transactions_account_idx ON ledger_transactions (account_id, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS ledger_transactions_pending_idx ON ledger_transactions (created_at) WHERE status = 'pending';
+
+-- Amounts are in the asset's base units; positive amounts credit the ledger account.
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id BIGSERIAL PRIMARY KEY,
+    transaction_id BIGINT NOT NULL REFERENCES ledger_transactions (id) ON DELETE CASCADE,
+    account VARCHAR(255) NOT NULL,
+    asset VARCHAR(16) NOT NULL,
+    decimals SMALLINT NOT NULL,
+    amount BIGINT NOT NULL,
+    leg VARCHAR(16) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ledger_entries_transaction_idx ON ledger_entries (transaction_id);
+CREATE INDEX IF NOT EXISTS ledger_entries_account_idx ON ledger_entries (account, asset);
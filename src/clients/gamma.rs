@@ -0,0 +1,101 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{decode, send_with_retry, ClientError, RetryPolicy};
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub id: String,
+    pub slug: Option<String>,
+    pub title: String,
+    pub description: Option<String>,
+    pub end_date: Option<String>,
+    #[serde(default)]
+    pub neg_risk: bool,
+    #[serde(default)]
+    pub markets: Vec<EventMarket>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EventMarket {
+    pub condition_id: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Market {
+    pub closed_time: Option<String>,
+    pub uma_end_date: Option<String>,
+}
+
+impl Market {
+    /// When the market closed upstream, falling back to the UMA resolution time for markets without a close time.
+    pub fn resolved_at(&self) -> Option<DateTime<Utc>> {
+        [&self.closed_time, &self.uma_end_date].into_iter()
+            .flatten()
+            .find_map(|date| parse_timestamp(date))
+    }
+}
+
+/// Gamma mixes RFC 3339 (`2024-11-06T05:46:22Z`) and Postgres-style (`2024-11-06 05:46:22+00`) timestamps.
+fn parse_timestamp(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .or_else(|_| DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S%#z"))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Client for the Polymarket Gamma API, which groups CLOB markets into events.
+pub struct GammaClient {
+    client: Client,
+    base_url: String,
+    retry: RetryPolicy,
+}
+
+impl GammaClient {
+    pub fn new(base_url: &str, timeout: Duration, retry: RetryPolicy) -> Self {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build Gamma client");
+
+        GammaClient {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            retry,
+        }
+    }
+
+    /// Open events, `limit` at a time starting at `offset`.
+    pub async fn events(&self, offset: usize, limit: usize) -> Result<Vec<Event>, ClientError> {
+        let url = format!("{}/events", self.base_url);
+        let offset = offset.to_string();
+        let limit = limit.to_string();
+
+        let response = send_with_retry(&self.retry, || {
+            self.client
+                .get(&url)
+                .query(&[("active", "true"), ("closed", "false"), ("offset", offset.as_str()), ("limit", limit.as_str())])
+        }).await?;
+
+        decode(response).await
+    }
+
+    /// A single market by condition id, including closed ones.
+    pub async fn market(&self, condition_id: &str) -> Result<Option<Market>, ClientError> {
+        let url = format!("{}/markets", self.base_url);
+
+        let response = send_with_retry(&self.retry, || {
+            self.client
+                .get(&url)
+                .query(&[("condition_ids", condition_id)])
+        }).await?;
+
+        let markets: Vec<Market> = decode(response).await?;
+        Ok(markets.into_iter().next())
+    }
+}
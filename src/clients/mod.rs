@@ -0,0 +1,114 @@
+pub mod gamma;
+pub mod polymarket;
+pub mod prediction_api;
+
+pub use gamma::GammaClient;
+pub use polymarket::PolymarketClient;
+pub use prediction_api::PredictionApi;
+
+use std::fmt;
+use std::time::Duration;
+use reqwest::{header::RETRY_AFTER, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+const MAX_BODY_LENGTH: usize = 500;
+
+#[derive(Debug)]
+pub enum ClientError {
+    Request(reqwest::Error),
+    Status { url: String, status: StatusCode, body: String },
+    Decode { url: String, error: String, body: String },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Request(e) => write!(f, "request failed: {}", e),
+            ClientError::Status { url, status, body } => write!(f, "{} returned {}: {}", url, status, body),
+            ClientError::Decode { url, error, body } => write!(f, "unexpected response from {}: {} (body: {})", url, error, body),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay * 2u32.saturating_pow(attempt.saturating_sub(1))
+    }
+}
+
+/// Sends the request built by `build`, retrying connection errors, timeouts, 429s and 5xx responses
+/// with exponential backoff. A `Retry-After` header takes precedence over the backoff delay.
+pub async fn send_with_retry<F>(policy: &RetryPolicy, build: F) -> Result<Response, ClientError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let delay = match build().send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+
+                if !retryable || attempt >= policy.attempts {
+                    let url = response.url().to_string();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(ClientError::Status { url, status, body: truncate(body) });
+                }
+
+                retry_after(&response).unwrap_or_else(|| policy.delay(attempt))
+            }
+            Err(e) => {
+                let retryable = e.is_timeout() || e.is_connect() || e.is_request();
+
+                if !retryable || attempt >= policy.attempts {
+                    return Err(ClientError::Request(e));
+                }
+
+                policy.delay(attempt)
+            }
+        };
+
+        tokio::time::sleep(delay).await;
+    }
+}
+
+pub async fn decode<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+    let url = response.url().to_string();
+    let body = response.text().await.map_err(ClientError::Request)?;
+
+    serde_json::from_str(&body).map_err(|e| ClientError::Decode { url, error: e.to_string(), body: truncate(body) })
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response.headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(|seconds| Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+fn truncate(mut body: String) -> String {
+    if body.len() > MAX_BODY_LENGTH {
+        let mut end = MAX_BODY_LENGTH;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+        body.push_str("...");
+    }
+
+    body
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use reqwest::Client;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{decode, send_with_retry, ClientError, RetryPolicy};
+
+/// The CLOB `/prices` endpoint accepts at most this many tokens per request.
+const PRICES_BATCH_SIZE: usize = 500;
+
+#[derive(Deserialize, Clone)]
+pub struct Market {
+    pub condition_id: String,
+    pub question: String,
+    pub description: Option<String>,
+    pub end_date_iso: Option<String>,
+    #[serde(default)]
+    pub active: bool,
+    pub closed: Option<bool>,
+    pub archived: Option<bool>,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub tokens: Vec<Token>,
+}
+
+impl Market {
+    /// Whether the market is still trading. A missing `closed` or `archived` flag counts as set.
+    pub fn is_open(&self) -> bool {
+        self.active && !self.closed.unwrap_or(true) && !self.archived.unwrap_or(true)
+    }
+
+    /// Token for the affirmative side of a binary market ("Yes" or "Up").
+    pub fn yes_token(&self) -> Option<&Token> {
+        self.tokens.iter().find(|token| matches!(token.outcome.to_lowercase().as_str(), "yes" | "up"))
+    }
+
+    /// Token for the negative side of a binary market ("No" or "Down").
+    pub fn no_token(&self) -> Option<&Token> {
+        self.tokens.iter().find(|token| matches!(token.outcome.to_lowercase().as_str(), "no" | "down"))
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Token {
+    pub token_id: String,
+    pub outcome: String,
+    #[serde(default, deserialize_with = "optional_decimal")]
+    pub price: Option<f64>,
+    #[serde(default)]
+    pub winner: bool,
+}
+
+#[derive(Deserialize)]
+pub struct MarketsPage {
+    #[serde(default)]
+    pub data: Vec<Market>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PriceRequest<'a> {
+    token_id: &'a str,
+    side: &'static str,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct PriceQuote {
+    #[serde(rename = "BUY", default, deserialize_with = "optional_decimal")]
+    pub buy: Option<f64>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OrderBook {
+    #[serde(default)]
+    pub bids: Vec<OrderLevel>,
+    #[serde(default)]
+    pub asks: Vec<OrderLevel>,
+}
+
+impl OrderBook {
+    /// Resting notional (price × size, in USDC) on both sides of the book.
+    pub fn depth(&self) -> f64 {
+        self.bids.iter()
+            .chain(self.asks.iter())
+            .map(|level| level.price * level.size)
+            .sum()
+    }
+
+    /// Bids from best (highest) to worst, regardless of the order the CLOB returned them in.
+    pub fn sorted_bids(&self) -> Vec<OrderLevel> {
+        let mut bids = self.bids.clone();
+        bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+        bids
+    }
+
+    /// Asks from best (lowest) to worst.
+    pub fn sorted_asks(&self) -> Vec<OrderLevel> {
+        let mut asks = self.asks.clone();
+        asks.sort_by(|a, b| a.price.total_cmp(&b.price));
+        asks
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct OrderLevel {
+    #[serde(deserialize_with = "decimal")]
+    pub price: f64,
+    #[serde(deserialize_with = "decimal")]
+    pub size: f64,
+}
+
+/// Client for the Polymarket CLOB REST API.
+pub struct PolymarketClient {
+    client: Client,
+    base_url: String,
+    retry: RetryPolicy,
+}
+
+impl PolymarketClient {
+    pub fn new(base_url: &str, timeout: Duration, retry: RetryPolicy) -> Self {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build Polymarket client");
+
+        PolymarketClient {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            retry,
+        }
+    }
+
+    pub async fn markets(&self, next_cursor: &str) -> Result<MarketsPage, ClientError> {
+        let url = format!("{}/markets", self.base_url);
+        let response = send_with_retry(&self.retry, || {
+            self.client
+                .get(&url)
+                .query(&[("next_cursor", next_cursor)])
+        }).await?;
+
+        decode(response).await
+    }
+
+    pub async fn market(&self, condition_id: &str) -> Result<Market, ClientError> {
+        let url = format!("{}/markets/{}", self.base_url, condition_id);
+        let response = send_with_retry(&self.retry, || self.client.get(&url)).await?;
+
+        decode(response).await
+    }
+
+    pub async fn book(&self, token_id: &str) -> Result<OrderBook, ClientError> {
+        let url = format!("{}/book", self.base_url);
+        let response = send_with_retry(&self.retry, || {
+            self.client
+                .get(&url)
+                .query(&[("token_id", token_id)])
+        }).await?;
+
+        decode(response).await
+    }
+
+    /// Fetches buy quotes for every token, batching requests as needed. Tokens without a quote are omitted.
+    pub async fn prices(&self, token_ids: &[String]) -> Result<HashMap<String, PriceQuote>, ClientError> {
+        let url = format!("{}/prices", self.base_url);
+        let mut prices = HashMap::new();
+
+        for chunk in token_ids.chunks(PRICES_BATCH_SIZE) {
+            let body: Vec<PriceRequest> = chunk.iter()
+                .map(|token_id| PriceRequest { token_id, side: "BUY" })
+                .collect();
+
+            let response = send_with_retry(&self.retry, || self.client.post(&url).json(&body)).await?;
+            let quotes: HashMap<String, PriceQuote> = decode(response).await?;
+            prices.extend(quotes);
+        }
+
+        Ok(prices)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Decimal {
+    Number(f64),
+    Text(String),
+}
+
+impl Decimal {
+    fn value<E: serde::de::Error>(self) -> Result<f64, E> {
+        match self {
+            Decimal::Number(value) => Ok(value),
+            Decimal::Text(text) => text.parse::<f64>().map_err(E::custom),
+        }
+    }
+}
+
+/// CLOB prices and sizes arrive as either JSON numbers or decimal strings.
+fn decimal<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Decimal::deserialize(deserializer)?.value()
+}
+
+fn optional_decimal<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Decimal>::deserialize(deserializer)?
+        .map(Decimal::value)
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use axum::extract::{Json, Query, State};
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use axum::Router;
+    use serde_json::{json, Value};
+
+    /// Serves `router` on a random local port and returns a client pointed at it.
+    async fn client(router: Router) -> PolymarketClient {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        PolymarketClient::new(&format!("http://{}/", address), Duration::from_secs(5), RetryPolicy {
+            attempts: 3,
+            base_delay: Duration::from_millis(10),
+        })
+    }
+
+    #[tokio::test]
+    async fn fetches_a_markets_page() {
+        let router = Router::new().route("/markets", get(|Query(query): Query<HashMap<String, String>>| async move {
+            assert_eq!(query.get("next_cursor").map(String::as_str), Some("MA=="));
+            Json(json!({
+                "data": [{
+                    "condition_id": "0xabc",
+                    "question": "Will it rain?",
+                    "active": true,
+                    "closed": false,
+                    "archived": false,
+                    "tokens": [
+                        {"token_id": "1", "outcome": "Yes", "price": "0.42"},
+                        {"token_id": "2", "outcome": "No", "price": 0.58}
+                    ]
+                }, {
+                    "condition_id": "0xdef",
+                    "question": "Will it snow?",
+                    "active": true
+                }],
+                "next_cursor": "LTE="
+            }))
+        }));
+
+        let page = client(router).await.markets("MA==").await.unwrap();
+
+        assert_eq!(page.next_cursor.as_deref(), Some("LTE="));
+        assert_eq!(page.data.len(), 2);
+        assert!(page.data[0].is_open());
+        assert_eq!(page.data[0].yes_token().and_then(|token| token.price), Some(0.42));
+        assert_eq!(page.data[0].no_token().and_then(|token| token.price), Some(0.58));
+        // Missing closed/archived flags count as set.
+        assert!(!page.data[1].is_open());
+    }
+
+    #[tokio::test]
+    async fn batches_price_requests() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route("/prices", post(|State(requests): State<Arc<AtomicUsize>>, Json(body): Json<Vec<Value>>| async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                let quotes: HashMap<String, Value> = body.iter()
+                    .map(|request| {
+                        assert_eq!(request["side"], "BUY");
+                        (request["token_id"].as_str().unwrap().to_string(), json!({"BUY": "0.5"}))
+                    })
+                    .collect();
+                Json(quotes)
+            }))
+            .with_state(Arc::clone(&requests));
+
+        let token_ids: Vec<String> = (0..PRICES_BATCH_SIZE + 1).map(|id| id.to_string()).collect();
+        let prices = client(router).await.prices(&token_ids).await.unwrap();
+
+        assert_eq!(prices.len(), PRICES_BATCH_SIZE + 1);
+        assert_eq!(prices["0"].buy, Some(0.5));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route("/book", get(|State(attempts): State<Arc<AtomicUsize>>| async move {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(StatusCode::SERVICE_UNAVAILABLE),
+                    _ => Ok(Json(json!({"bids": [{"price": "0.4", "size": "10"}], "asks": [{"price": 0.6, "size": 5}]}))),
+                }
+            }))
+            .with_state(Arc::clone(&attempts));
+
+        let book = client(router).await.book("1").await.unwrap();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert!((book.depth() - 7.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route("/markets/{condition_id}", get(|State(attempts): State<Arc<AtomicUsize>>| async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                (StatusCode::NOT_FOUND, "market not found")
+            }))
+            .with_state(Arc::clone(&attempts));
+
+        let result = client(router).await.market("0xabc").await;
+
+        assert!(matches!(result, Err(ClientError::Status { status, ref body, .. }) if status == StatusCode::NOT_FOUND && body == "market not found"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_malformed_responses() {
+        let router = Router::new().route("/markets", get(|| async { "not json" }));
+
+        let result = client(router).await.markets("MA==").await;
+
+        assert!(matches!(result, Err(ClientError::Decode { ref body, .. }) if body == "not json"));
+    }
+}
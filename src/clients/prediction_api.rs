@@ -0,0 +1,142 @@
+use std::time::Duration;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{decode, send_with_retry, truncate, ClientError, RetryPolicy};
+
+#[derive(Serialize)]
+pub struct CreateEventRequest {
+    pub title: String,
+    pub description: String,
+    pub cutoff: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateEventResponse {
+    pub event_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct EventPredictions {
+    #[serde(default)]
+    pub predictions: Vec<MinerPrediction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinerPrediction {
+    /// Normally a decimal string; anything else is skipped when aggregating rather than failing the whole response.
+    #[serde(default)]
+    pub predicted_outcome: Option<Value>,
+    pub miner_hotkey: Option<String>,
+    pub miner_uid: Option<Value>,
+}
+
+impl MinerPrediction {
+    pub fn miner_id(&self) -> Option<String> {
+        match (&self.miner_hotkey, &self.miner_uid) {
+            (Some(hotkey), _) => Some(hotkey.clone()),
+            (None, Some(Value::String(uid))) => Some(uid.clone()),
+            (None, Some(Value::Number(uid))) => Some(uid.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CommunityPrediction {
+    pub community_prediction: Option<f64>,
+}
+
+/// Client for the upstream prediction API (`{API_URL}/api/v2`).
+pub struct PredictionApi {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    retry: RetryPolicy,
+}
+
+impl PredictionApi {
+    pub fn new(base_url: &str, api_key: &str, timeout: Duration, retry: RetryPolicy) -> Self {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build prediction API client");
+
+        PredictionApi {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            retry,
+        }
+    }
+
+    pub async fn create_event(&self, request: &CreateEventRequest) -> Result<CreateEventResponse, ClientError> {
+        let url = format!("{}/api/v2/events", self.base_url);
+
+        // Creating an event is not idempotent, so a timed-out attempt must not be replayed.
+        let retry = RetryPolicy { attempts: 1, ..self.retry.clone() };
+        let response = send_with_retry(&retry, || {
+            self.client
+                .post(&url)
+                .header("X-API-Key", &self.api_key)
+                .json(request)
+        }).await?;
+
+        decode(response).await
+    }
+
+    /// Returns the miner predictions for an event along with the raw payload, which is stored verbatim in `outcomes.raw`.
+    pub async fn predictions(&self, event_id: &str) -> Result<(EventPredictions, Value), ClientError> {
+        let url = format!("{}/api/v2/validator/events/{}/predictions", self.base_url, event_id);
+        let response = send_with_retry(&self.retry, || {
+            self.client
+                .get(&url)
+                .header("X-API-Key", &self.api_key)
+        }).await?;
+
+        let raw: Value = decode(response).await?;
+        let predictions = serde_json::from_value(raw.clone())
+            .map_err(|e| ClientError::Decode { url, error: e.to_string(), body: truncate(raw.to_string()) })?;
+
+        Ok((predictions, raw))
+    }
+
+    pub async fn community_prediction(&self, event_id: &str) -> Result<CommunityPrediction, ClientError> {
+        let url = format!("{}/api/v2/validator/events/{}/community_prediction", self.base_url, event_id);
+        let response = send_with_retry(&self.retry, || {
+            self.client
+                .get(&url)
+                .header("X-API-Key", &self.api_key)
+        }).await?;
+
+        decode(response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::aggregation::miner_forecasts;
+
+    #[test]
+    fn malformed_miner_entries_are_skipped() {
+        let predictions: EventPredictions = serde_json::from_value(serde_json::json!({
+            "predictions": [
+                {"predictedOutcome": "0.7", "minerHotkey": "5Hot"},
+                {"predictedOutcome": 0.4, "minerUid": 12},
+                {"predictedOutcome": null, "minerUid": "13"},
+                {"minerUid": 14},
+                {"predictedOutcome": "not a number"}
+            ]
+        })).unwrap();
+
+        let forecasts = miner_forecasts(&predictions.predictions);
+
+        assert_eq!(predictions.predictions.len(), 5);
+        assert_eq!(forecasts.len(), 1);
+        assert_eq!(forecasts[0].miner.as_deref(), Some("5Hot"));
+        assert_eq!(forecasts[0].value, 0.7);
+    }
+}
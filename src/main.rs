@@ -1,6 +1,7 @@
 use std::sync::Arc;
 use tokio;
 
+mod clients;
 mod models;
 mod utilities;
 mod routes;
@@ -11,15 +12,43 @@ use crate::prelude::*;
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        if let Err(e) = migrations::migrate_command(&args[1..]).await {
+            eprintln!("Migration failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.first().map(String::as_str) == Some("keys") {
+        if let Err(e) = encryption::keys_command(&args[1..]).await {
+            eprintln!("Key command failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.first().map(String::as_str) == Some("signer") {
+        if let Err(e) = signer::signer_command().await {
+            eprintln!("Signer failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let app_state = AppState::create().await;
     let app = router::app_router(Arc::clone(&app_state));
 
     let address = format!("{}:{}", app_state.config.server_ip, app_state.config.server_port);
     let listener = tokio::net::TcpListener::bind(address).await.unwrap();
 
+    let feed_state = Arc::clone(&app_state);
     tokio::spawn(async move {
-        tasks::start_tasks(app_state).await;
+        run_price_feed(feed_state).await;
     });
 
+    start_scheduler(app_state);
+
     axum::serve(listener, app.into_make_service()).await.unwrap();
 }
\ No newline at end of file
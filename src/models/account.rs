@@ -10,7 +10,5 @@ pub struct Account {
     pub session_id: Option<String>,
     pub email: String,
     pub active: bool,
-    pub wallet_address: Option<String>,
-    pub wallet_secret: Option<String>,
     pub created_at: NaiveDateTime,
 }
\ No newline at end of file
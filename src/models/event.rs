@@ -0,0 +1,43 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct Event {
+    pub event_id: String,
+    pub slug: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub end_date: Option<NaiveDateTime>,
+    pub neg_risk: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EventResponse {
+    #[serde(flatten)]
+    pub event: Event,
+    pub markets: Vec<EventMarket>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EventMarket {
+    pub condition_id: String,
+    pub question: String,
+    pub end_date: NaiveDateTime,
+    pub resolved: bool,
+    pub outcome: Option<String>,
+    pub predictions: Vec<EventPrediction>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EventPrediction {
+    pub prediction_id: String,
+    pub token_id: Option<String>,
+    pub label: Option<String>,
+    pub weighted: Option<f64>,
+    pub community: Option<f64>,
+    pub price: Option<f64>,
+    pub as_of: Option<NaiveDateTime>,
+}
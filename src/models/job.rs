@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct JobRun {
+    pub id: i64,
+    pub job: String,
+    pub triggered_by: String,
+    pub status: String,
+    pub counters: Option<Value>,
+    pub error: Option<String>,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JobResponse {
+    pub name: String,
+    pub schedule: String,
+    pub next_run: Option<NaiveDateTime>,
+    pub last_run: Option<JobRun>,
+}
+
+#[derive(Deserialize)]
+pub struct JobRunsQuery {
+    pub job: Option<String>,
+    pub limit: Option<i64>,
+}
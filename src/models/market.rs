@@ -7,9 +7,121 @@ pub struct Market {
     pub condition_id: String,
     pub question: String,
     pub description: String,
-    pub tags: String,
+    pub tags: Vec<String>,
     pub yes_token_id: Option<String>,
     pub no_token_id: Option<String>,
     pub end_date: NaiveDateTime,
     pub created_at: NaiveDateTime,
-}
\ No newline at end of file
+    pub resolved: bool,
+    pub outcome: Option<String>,
+    pub winning_token_id: Option<String>,
+    pub final_price: Option<f64>,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub event_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct MarketListing {
+    pub condition_id: String,
+    pub question: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub yes_token_id: Option<String>,
+    pub no_token_id: Option<String>,
+    pub end_date: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub resolved: bool,
+    pub outcome: Option<String>,
+    pub winning_token_id: Option<String>,
+    pub final_price: Option<f64>,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub event_id: Option<String>,
+    pub divergence: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct MarketOutcome {
+    pub token_id: String,
+    pub condition_id: String,
+    pub outcome: String,
+    pub position: i32,
+    pub winner: bool,
+}
+
+/// One outcome of a market together with its prediction, if any, and current price.
+#[derive(Serialize, Deserialize)]
+pub struct MarketOutcomeResponse {
+    pub token_id: String,
+    pub outcome: String,
+    pub position: i32,
+    pub winner: bool,
+    pub price: Option<f64>,
+    pub prediction_id: Option<String>,
+    pub weighted: Option<f64>,
+    pub community: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct MarketBookQuery {
+    pub depth: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    pub markets: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BookLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+/// Top of book and depth for a single outcome token. Depth is the USDC notional resting in the returned levels.
+#[derive(Serialize, Deserialize)]
+pub struct TokenBook {
+    pub token_id: String,
+    pub outcome: String,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub mid: Option<f64>,
+    pub spread: Option<f64>,
+    pub bid_depth: f64,
+    pub ask_depth: f64,
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+}
+
+#[derive(Deserialize)]
+pub struct MarketsQuery {
+    pub tag: Option<String>,
+    pub exclude_tag: Option<String>,
+    pub q: Option<String>,
+    pub sort: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Keyset position of the last market on a page. Only the field matching `sort` is set.
+#[derive(Serialize, Deserialize)]
+pub struct MarketCursor {
+    pub sort: String,
+    pub timestamp: Option<NaiveDateTime>,
+    pub divergence: Option<f64>,
+    pub condition_id: String,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct MarketChange {
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub changed_at: NaiveDateTime,
+    pub after_prediction: bool,
+}
@@ -0,0 +1,27 @@
+use chrono::{NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct MinerSummary {
+    pub miner_id: String,
+    pub forecasts: i64,
+    pub predictions: i64,
+    pub coverage: Option<f64>,
+    pub first_seen: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+    pub resolved: i64,
+    pub brier: Option<f64>,
+    pub hit_rate: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct MinerForecastRecord {
+    pub prediction_id: String,
+    pub condition_id: String,
+    pub question: String,
+    pub predicted_outcome: f64,
+    pub created_at: NaiveDateTime,
+    pub resolved: bool,
+    pub outcome: Option<String>,
+}
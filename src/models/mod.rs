@@ -1,9 +1,17 @@
 pub mod account;
+pub mod event;
+pub mod job;
 pub mod market;
+pub mod miner;
 pub mod prediction;
+pub mod transaction;
 pub mod wallet;
 
 pub use account::*;
+pub use event::*;
+pub use job::*;
 pub use market::*;
+pub use miner::*;
 pub use prediction::*;
+pub use transaction::*;
 pub use wallet::*;
\ No newline at end of file
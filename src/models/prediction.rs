@@ -1,4 +1,4 @@
-use chrono::{NaiveDateTime};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
@@ -6,24 +6,103 @@ use sqlx::FromRow;
 pub struct Prediction {
     pub prediction_id: String,
     pub condition_id: String,
+    pub token_id: Option<String>,
     pub end_date: NaiveDateTime,
 }
 
+#[derive(Deserialize)]
+pub struct CreatePredictionQuery {
+    pub token_id: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct PredictionResponse {
     pub prediction_id: String,
     pub condition_id: String,
+    pub token_id: Option<String>,
+    pub label: Option<String>,
     pub question: String,
     pub description: String,
-    pub tags: String,
+    pub tags: Vec<String>,
     pub end_date: NaiveDateTime,
     pub created_at: NaiveDateTime,
+    pub resolved: bool,
+    pub outcome: Option<String>,
+    pub final_price: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct PredictionResultResponse {
     pub prediction_id: String,
     pub condition_id: String,
+    pub token_id: Option<String>,
+    pub weighted: f64,
+    pub community: f64,
+}
+
+/// Latest stored outcome for a prediction. `stale` is set once `as_of` is older than the configured maximum age.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PredictionResult {
+    pub weighted: f64,
+    pub community: f64,
+    pub aggregator: String,
+    pub as_of: NaiveDateTime,
+    pub stale: bool,
+}
+
+#[derive(Deserialize)]
+pub struct PredictionResultQuery {
+    pub aggregator: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PredictionHistoricalQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub bucket: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OutcomePoint {
+    pub timestamp: NaiveDateTime,
+    pub weighted: f64,
+    pub community: f64,
+    pub price: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OutcomeBucket {
+    pub timestamp: NaiveDateTime,
+    pub count: i64,
+    pub weighted_min: f64,
+    pub weighted_max: f64,
+    pub weighted_mean: f64,
+    pub community_min: f64,
+    pub community_max: f64,
+    pub community_mean: f64,
+    pub price_min: Option<f64>,
+    pub price_max: Option<f64>,
+    pub price_mean: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct PredictionEdgesQuery {
+    pub source: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PredictionEdge {
+    pub prediction_id: String,
+    pub condition_id: String,
+    pub token_id: String,
+    pub label: Option<String>,
+    pub question: String,
     pub weighted: f64,
     pub community: f64,
-}
\ No newline at end of file
+    pub price: f64,
+    pub divergence: f64,
+    pub abs_divergence: f64,
+    pub direction: String,
+    pub end_date: NaiveDateTime,
+    pub hours_to_close: f64,
+}
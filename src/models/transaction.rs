@@ -0,0 +1,40 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+
+#[derive(Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub account: String,
+    pub asset: String,
+    pub decimals: i16,
+    pub amount: i64,
+    pub leg: String,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct LedgerTransaction {
+    pub id: i64,
+    pub kind: String,
+    pub status: String,
+    pub signature: Option<String>,
+    pub external_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub entries: Json<Vec<LedgerEntry>>,
+}
+
+#[derive(Deserialize)]
+pub struct TransactionsQuery {
+    pub kind: Option<String>,
+    pub status: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TransactionCursor {
+    pub created_at: NaiveDateTime,
+    pub id: i64,
+}
@@ -7,6 +7,8 @@ pub struct Wallet {
     pub account_id: String,
     pub subscription_id: String,
     pub address: String,
-    pub secret: String,
+    pub secret: Option<String>,
+    pub secret_key: Option<String>,
+    pub key_version: Option<i32>,
     pub created_at: NaiveDateTime,
 }
\ No newline at end of file
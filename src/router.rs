@@ -1,17 +1,18 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{Html, IntoResponse},
     routing::{get, post},
     Router,
 };
-use chrono::SecondsFormat;
+use chrono::{Duration, Utc};
 use reqwest::Client;
 use serde_json::{json, Value};
 use std::sync::Arc;
 use tower_http::{trace, trace::TraceLayer};
 use tracing::Level;
 
+use crate::clients::prediction_api::EventPredictions;
 use crate::prelude::*;
 
 pub fn app_router(app_state: Arc<AppState>) -> Router {
@@ -29,16 +30,32 @@ pub fn app_router(app_state: Arc<AppState>) -> Router {
         .route("/auth/session", get(get_session))
         .route("/api/v1/markets", get(get_markets).post(create_markets))
         .route("/api/v1/market/{id}/price", get(get_market_price))
+        .route("/api/v1/market/{id}/book", get(get_market_book))
+        .route("/api/v1/market/{id}/outcomes", get(get_market_outcomes))
+        .route("/api/v1/market/{id}/changes", get(get_market_changes))
         .route("/api/v1/markets/prices", get(get_market_prices))
+        .route("/api/v1/markets/tags", get(get_market_tags))
+        .route("/api/v1/events", get(get_events).post(create_events))
+        .route("/api/v1/events/{id}", get(get_event))
+        .route("/api/v1/stream", get(get_stream))
         .route("/api/v1/prediction/{id}", get(get_prediction).post(create_prediction))
         .route("/api/v1/prediction/{id}/result", get(get_prediction_result))
+        .route("/api/v1/prediction/{id}/result/refresh", post(refresh_prediction_result))
         .route("/api/v1/prediction/{id}/historical", get(get_prediction_historical))
         .route("/api/v1/predictions/results", get(get_prediction_results))
+        .route("/api/v1/predictions/edges", get(get_prediction_edges))
+        .route("/api/v1/miners", get(get_miners))
+        .route("/api/v1/miners/{id}", get(get_miner))
+        .route("/api/v1/stats/calibration", get(get_calibration))
         .route("/api/v1/rates", get(get_rates))
+        .route("/api/v1/admin/cache", get(get_cache_stats))
+        .route("/api/v1/admin/jobs", get(get_jobs))
+        .route("/api/v1/admin/jobs/{name}/run", post(run_job))
         .route("/api/v1/wallet/address", get(get_address))
         .route("/api/v1/wallet/balance", get(get_balance))
         .route("/api/v1/wallet/withdraw", post(create_withdraw))
         .route("/api/v1/wallet/swap", post(create_swap))
+        .route("/api/v1/wallet/transactions", get(get_transactions))
         .route("/api/v1/webhook/tatum", post(tatum_webhook))
         .layer(
             TraceLayer::new_for_http()
@@ -56,26 +73,159 @@ async fn prediction() -> Html<&'static str> {
     Html(include_str!("../frontend/prediction.html"))
 }
 
-async fn get_markets(State(state): State<Arc<AppState>>, auth: Auth) -> impl IntoResponse {
+async fn get_markets(State(state): State<Arc<AppState>>, auth: Auth, Query(params): Query<MarketsQuery>) -> impl IntoResponse {
+    let tags = parse_tags(params.tag.as_deref());
+    let excluded_tags = parse_tags(params.exclude_tag.as_deref());
+    let search = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+
+    let sort = params.sort.as_deref().unwrap_or("end_date");
+    if !["end_date", "created_at", "divergence"].contains(&sort) {
+        return JsonResponse::error("Invalid sort, expected end_date, created_at or divergence", StatusCode::BAD_REQUEST);
+    }
+
+    let limit = match page_size(params.limit) {
+        Some(limit) => limit,
+        None => return JsonResponse::error("Invalid limit", StatusCode::BAD_REQUEST),
+    };
+
+    let cursor = match params.cursor.as_deref() {
+        Some(cursor) => match decode_cursor::<MarketCursor>(cursor) {
+            Some(cursor) if cursor.sort == sort => Some(cursor),
+            _ => return JsonResponse::error("Invalid cursor", StatusCode::BAD_REQUEST),
+        },
+        None => None,
+    };
+
+    let cursor_timestamp = cursor.as_ref().and_then(|cursor| cursor.timestamp);
+    let cursor_divergence = cursor.as_ref().and_then(|cursor| cursor.divergence);
+    let cursor_id = cursor.as_ref().map(|cursor| cursor.condition_id.clone());
+
+    // A market's divergence is the largest across its predicted outcomes. Markets without a stored outcome
+    // and price sort last under divergence (key -1).
     let result = sqlx::query_as!(
-        Market,
-        "SELECT * FROM markets WHERE end_date >= NOW() ORDER BY end_date ASC")
+        MarketListing,
+        r#"WITH listings AS (
+            SELECT m.*, d.divergence, COALESCE(d.divergence, -1) AS divergence_key
+            FROM markets m
+            LEFT JOIN LATERAL (
+                SELECT MAX(ROUND(ABS(o.weighted - pr.price)::numeric, 4))::float8 AS divergence
+                FROM predictions p
+                JOIN LATERAL (
+                    SELECT weighted FROM outcomes
+                    WHERE prediction_id = p.prediction_id
+                    ORDER BY created_at DESC
+                    LIMIT 1
+                ) o ON TRUE
+                JOIN LATERAL (
+                    SELECT price FROM prices
+                    WHERE token_id = p.token_id
+                    ORDER BY created_at DESC
+                    LIMIT 1
+                ) pr ON TRUE
+                WHERE p.condition_id = m.condition_id
+            ) d ON TRUE
+            WHERE m.end_date >= NOW()
+            AND ($1::text[] IS NULL OR m.tags && $1)
+            AND ($2::text[] IS NULL OR NOT m.tags && $2)
+            AND ($3::text IS NULL OR to_tsvector('english', m.question || ' ' || m.description) @@ websearch_to_tsquery('english', $3))
+        )
+        SELECT condition_id AS "condition_id!", question AS "question!", description AS "description!", tags AS "tags!",
+            yes_token_id, no_token_id, end_date AS "end_date!", created_at AS "created_at!", resolved AS "resolved!",
+            outcome, winning_token_id, final_price, resolved_at, event_id, divergence
+        FROM listings
+        WHERE $7::text IS NULL
+        OR ($4 = 'end_date' AND (end_date, condition_id) > ($5, $7))
+        OR ($4 = 'created_at' AND (created_at, condition_id) < ($5, $7))
+        OR ($4 = 'divergence' AND (divergence_key, condition_id) < ($6, $7))
+        ORDER BY
+            CASE WHEN $4 = 'end_date' THEN end_date END ASC,
+            CASE WHEN $4 = 'created_at' THEN created_at END DESC,
+            CASE WHEN $4 = 'divergence' THEN divergence_key END DESC,
+            CASE WHEN $4 = 'end_date' THEN condition_id END ASC,
+            CASE WHEN $4 <> 'end_date' THEN condition_id END DESC
+        LIMIT $8"#,
+        tags.as_deref(),
+        excluded_tags.as_deref(),
+        search,
+        sort,
+        cursor_timestamp,
+        cursor_divergence,
+        cursor_id,
+        limit + 1)
+        .fetch_all(&*state.pool)
+        .await;
+
+    let mut markets = match result {
+        Ok(markets) => markets,
+        Err(e) => {
+            eprintln!("Error fetching markets: {}", e);
+            return JsonResponse::error("Failed to fetch markets", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let next_cursor = if markets.len() as i64 > limit {
+        markets.truncate(limit as usize);
+        markets.last().map(|market| encode_cursor(&MarketCursor {
+            sort: sort.to_string(),
+            timestamp: match sort {
+                "end_date" => Some(market.end_date),
+                "created_at" => Some(market.created_at),
+                _ => None,
+            },
+            divergence: (sort == "divergence").then(|| market.divergence.unwrap_or(-1.0)),
+            condition_id: market.condition_id.clone(),
+        }))
+    } else {
+        None
+    };
+
+    JsonResponse::success_with_meta(markets, PageMeta { limit, next_cursor }, StatusCode::OK)
+}
+
+async fn get_market_tags(State(state): State<Arc<AppState>>, _auth: Auth) -> impl IntoResponse {
+    let result = sqlx::query_as!(
+        TagCount,
+        r#"SELECT tag AS "tag!", COUNT(*) AS "count!"
+        FROM markets, UNNEST(tags) AS tag
+        WHERE end_date >= NOW()
+        GROUP BY tag
+        ORDER BY 2 DESC, 1 ASC"#)
         .fetch_all(&*state.pool)
         .await;
 
     match result {
-        Ok(markets) => JsonResponse::success(markets, StatusCode::OK),
-        Err(_) => JsonResponse::error("Failed to fetch markets", StatusCode::INTERNAL_SERVER_ERROR)
+        Ok(tags) => JsonResponse::success(tags, StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error fetching market tags: {}", e);
+            JsonResponse::error("Failed to fetch market tags", StatusCode::INTERNAL_SERVER_ERROR)
+        }
     }
 }
 
+/// Parses a comma-separated tag filter. A market matches if it has any of the listed tags.
+fn parse_tags(tags: Option<&str>) -> Option<Vec<String>> {
+    let tags: Vec<String> = tags?
+        .split(',')
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+
+    if tags.is_empty() {
+        return None;
+    }
+
+    Some(tags)
+}
+
 async fn get_prediction(State(state): State<Arc<AppState>>, auth: Auth, Path(id): Path<String>) -> impl IntoResponse {
     let result = sqlx::query_as!(
         PredictionResponse,
-        "SELECT p.prediction_id, m.condition_id, m.question, m.description, m.tags, m.end_date, m.created_at
+        "SELECT p.prediction_id, m.condition_id, p.token_id, mo.outcome AS label, m.question, m.description, m.tags, m.end_date, m.created_at, m.resolved, m.outcome, m.final_price
         FROM markets m
         JOIN predictions p ON m.condition_id = p.condition_id
+        LEFT JOIN market_outcomes mo ON mo.token_id = p.token_id
         WHERE p.prediction_id = $1 OR m.condition_id = $1
+        ORDER BY (p.prediction_id = $1) DESC, mo.position
         LIMIT 1",
         id)
         .fetch_optional(&*state.pool)
@@ -88,7 +238,7 @@ async fn get_prediction(State(state): State<Arc<AppState>>, auth: Auth, Path(id)
     }
 }
 
-async fn create_prediction(State(state): State<Arc<AppState>>, auth: Auth, Path(condition_id): Path<String>) -> impl IntoResponse {
+async fn create_prediction(State(state): State<Arc<AppState>>, auth: Auth, Path(condition_id): Path<String>, Query(params): Query<CreatePredictionQuery>) -> impl IntoResponse {
     let result = sqlx::query_as!(
         Market,
         "SELECT * FROM markets WHERE condition_id = $1 AND end_date > NOW()",
@@ -102,201 +252,270 @@ async fn create_prediction(State(state): State<Arc<AppState>>, auth: Auth, Path(
         Err(_) => return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR)
     };
 
-    let existing_prediction = sqlx::query!(
-        "SELECT prediction_id FROM predictions WHERE condition_id = $1",
-        condition_id)
-        .fetch_optional(&*state.pool)
-        .await;
-
-    match existing_prediction {
-        Ok(None) => (),
-        Ok(Some(_)) => return JsonResponse::error("Prediction already exists for this market", StatusCode::CONFLICT),
-        Err(_) => return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR)
+    if let Some(token_id) = &params.token_id {
+        let outcome = sqlx::query_scalar!(
+            "SELECT token_id FROM market_outcomes WHERE condition_id = $1 AND token_id = $2",
+            condition_id,
+            token_id)
+            .fetch_optional(&*state.pool)
+            .await;
+
+        match outcome {
+            Ok(Some(_)) => (),
+            Ok(None) => return JsonResponse::error("Outcome not found", StatusCode::NOT_FOUND),
+            Err(_) => return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR)
+        }
     }
 
-    let cutoff = market.end_date.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true);
-
-    let request_body = json!({
-        "title": market.question,
-        "description": market.description,
-        "cutoff": cutoff,
-    });
+    let targets = match prediction_targets(&state.pool, &market, params.token_id.as_deref()).await {
+        Ok(targets) if targets.is_empty() => return JsonResponse::error("Prediction already exists for this market", StatusCode::CONFLICT),
+        Ok(targets) => targets,
+        Err(_) => return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR)
+    };
 
-    let client = Client::new();
-    let api_url = format!("{}/api/v2/events", &state.config.api_url);
-
-    let response = match client
-        .post(api_url)
-        .header("X-API-Key", &state.config.api_key)
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await {
-            Ok(resp) => resp,
+    let mut predictions = Vec::new();
+    for outcome in &targets {
+        match create_outcome_prediction(&state, &market, outcome).await {
+            Ok(prediction) => predictions.push(prediction),
             Err(e) => {
-                eprintln!("Error making API request: {}", e);
+                eprintln!("Error creating prediction for {} ({}): {}", condition_id, outcome.outcome, e);
                 return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR);
             }
-        };
-
-    if !response.status().is_success() {
-        let error = match response.text().await {
-            Ok(text) => format!("API request failed for prediction: {}\nRequest: {}\nResponse: {}", condition_id, request_body, text),
-            Err(_) => format!("API request failed for prediction: {}\nRequest: {}\nError: Unable to read response", condition_id, request_body)
-        };
-        eprintln!("{}", error);
-        return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR);
-    }
-
-    let prediction_response = match response.json::<Value>().await {
-        Ok(json) => json,
-        Err(e) => {
-            eprintln!("Failed to parse API response for prediction: {}\nRequest: {}\nError: {}", condition_id, request_body, e);
-            return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR);
         }
-    };
-
-    let prediction_id = match prediction_response.get("event_id").and_then(|v| v.as_str()) {
-        Some(id) => id.to_string(),
-        None => {
-            eprintln!("No event_id in API response for prediction: {}\nRequest: {}\nResponse: {}", condition_id, request_body, prediction_response);
-            return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
-
-    let end_date = market.end_date;
-
-    let result = sqlx::query!(
-        "INSERT INTO predictions (prediction_id, condition_id, end_date, created_at) VALUES ($1, $2, $3, NOW())",
-        prediction_id,
-        condition_id,
-        end_date)
-        .execute(&*state.pool)
-        .await;
-
-    match result {
-        Ok(_) => JsonResponse::success(Prediction { prediction_id, condition_id, end_date }, StatusCode::CREATED),
-        Err(_) => JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR)
     }
+
+    JsonResponse::success(predictions, StatusCode::CREATED)
 }
 
-async fn get_prediction_result(State(state): State<Arc<AppState>>, auth: Auth, Path(id): Path<String>) -> impl IntoResponse {
+async fn get_prediction_result(State(state): State<Arc<AppState>>, auth: Auth, Path(id): Path<String>, Query(params): Query<PredictionResultQuery>) -> impl IntoResponse {
     let prediction = match sqlx::query!(
-        "SELECT prediction_id FROM predictions WHERE prediction_id = $1 OR condition_id = $1",
+        "SELECT p.prediction_id
+        FROM predictions p
+        LEFT JOIN market_outcomes mo ON mo.token_id = p.token_id
+        WHERE p.prediction_id = $1 OR p.condition_id = $1
+        ORDER BY (p.prediction_id = $1) DESC, mo.position
+        LIMIT 1",
         id)
         .fetch_optional(&*state.pool)
         .await {
             Ok(Some(pred)) => pred,
             Ok(None) => return JsonResponse::error("Prediction not found", StatusCode::NOT_FOUND),
-            Err(_) => return JsonResponse::error("Failed to create prediction", StatusCode::INTERNAL_SERVER_ERROR)
+            Err(_) => return JsonResponse::error("Failed to fetch predictions", StatusCode::INTERNAL_SERVER_ERROR)
         };
 
-    let client = Client::new();
-
-    let weighted_url = format!("{}/api/v2/validator/events/{}/predictions", &state.config.api_url, prediction.prediction_id);
-    let weighted_response = match client
-        .get(&weighted_url)
-        .header("X-API-Key", &state.config.api_key)
-        .send()
+    let latest = match sqlx::query!(
+        "SELECT weighted, community, aggregator, raw, created_at FROM outcomes
+        WHERE prediction_id = $1
+        ORDER BY created_at DESC
+        LIMIT 1",
+        prediction.prediction_id)
+        .fetch_optional(&*state.pool)
         .await {
-            Ok(resp) => resp,
-            Err(e) => {
-                eprintln!("Task: API request failed for weighted prediction for {}: {}", prediction.prediction_id, e);
-                return JsonResponse::error("Failed to fetch predictions", StatusCode::INTERNAL_SERVER_ERROR);
-            }
+            Ok(Some(latest)) => latest,
+            Ok(None) => return JsonResponse::error("No predictions available yet", StatusCode::UNPROCESSABLE_ENTITY),
+            Err(_) => return JsonResponse::error("Failed to fetch predictions", StatusCode::INTERNAL_SERVER_ERROR)
         };
 
-    if !weighted_response.status().is_success() {
-        let error = match weighted_response.text().await {
-            Ok(text) => format!("Task: API request failed for weighted prediction for {}: {}", prediction.prediction_id, text),
-            Err(_) => "API request failed for weighted prediction. Error: Unable to read error response".to_string()
-        };
-        eprintln!("{}", error);
-        return JsonResponse::error("Failed to fetch predictions", StatusCode::INTERNAL_SERVER_ERROR);
-    }
+    let stale = latest.created_at < Utc::now().naive_utc() - Duration::minutes(state.config.result_max_age_minutes);
+
+    let name = match params.aggregator {
+        Some(name) if name != latest.aggregator => name,
+        _ => return JsonResponse::success(PredictionResult {
+            weighted: latest.weighted,
+            community: latest.community,
+            aggregator: latest.aggregator,
+            as_of: latest.created_at,
+            stale,
+        }, StatusCode::OK)
+    };
 
-    let weighted_json = match weighted_response.json::<Value>().await {
-        Ok(json) => json,
+    let aggregator = match build_aggregator(&state.pool, &name).await {
+        Some(aggregator) => aggregator,
+        None => return JsonResponse::error("Invalid aggregator", StatusCode::BAD_REQUEST)
+    };
+
+    let stored = match serde_json::from_value::<EventPredictions>(latest.raw) {
+        Ok(stored) => stored,
         Err(e) => {
-            eprintln!("Task: Error parsing weighted predictions response for {}: {}", prediction.prediction_id, e);
+            eprintln!("Error parsing stored predictions for {}: {}", prediction.prediction_id, e);
             return JsonResponse::error("Failed to fetch predictions", StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
-    let outcomes = match weighted_json.get("predictions").and_then(|v| v.as_array()) {
-        Some(preds) => preds,
+    let weighted = match aggregator.aggregate(&miner_forecasts(&stored.predictions)) {
+        Some(value) => (value * 10000.0).round() / 10000.0,
         None => return JsonResponse::error("No predictions available yet", StatusCode::UNPROCESSABLE_ENTITY)
     };
 
-    if outcomes.is_empty() {
-        return JsonResponse::error("No predictions available yet", StatusCode::UNPROCESSABLE_ENTITY);
-    }
+    JsonResponse::success(PredictionResult {
+        weighted,
+        community: latest.community,
+        aggregator: aggregator.name().to_string(),
+        as_of: latest.created_at,
+        stale,
+    }, StatusCode::OK)
+}
 
-    let mut sum = 0.0;
-    let mut count = 0;
+/// Fetches a new outcome from the upstream API, unless the latest stored one is still within the freshness window.
+async fn refresh_prediction_result(State(state): State<Arc<AppState>>, _auth: Auth, Path(id): Path<String>) -> impl IntoResponse {
+    let prediction = match sqlx::query!(
+        "SELECT p.prediction_id, p.condition_id, o.weighted AS \"weighted?\", o.community AS \"community?\",
+            o.aggregator AS \"aggregator?\", o.created_at AS \"created_at?\"
+        FROM predictions p
+        LEFT JOIN LATERAL (
+            SELECT weighted, community, aggregator, created_at FROM outcomes
+            WHERE prediction_id = p.prediction_id
+            ORDER BY created_at DESC
+            LIMIT 1
+        ) o ON TRUE
+        LEFT JOIN market_outcomes mo ON mo.token_id = p.token_id
+        WHERE p.prediction_id = $1 OR p.condition_id = $1
+        ORDER BY (p.prediction_id = $1) DESC, mo.position
+        LIMIT 1",
+        id)
+        .fetch_optional(&*state.pool)
+        .await {
+            Ok(Some(pred)) => pred,
+            Ok(None) => return JsonResponse::error("Prediction not found", StatusCode::NOT_FOUND),
+            Err(_) => return JsonResponse::error("Failed to refresh predictions", StatusCode::INTERNAL_SERVER_ERROR)
+        };
 
-    for outcome in outcomes {
-        if let Some(pred) = outcome.get("predictedOutcome").and_then(|v| v.as_str()) {
-            if let Ok(value) = pred.parse::<f64>() {
-                sum += value;
-                count += 1;
-            }
+    let fresh_after = Utc::now().naive_utc() - Duration::minutes(state.config.result_max_age_minutes);
+    if let (Some(weighted), Some(community), Some(aggregator), Some(as_of)) = (prediction.weighted, prediction.community, prediction.aggregator, prediction.created_at) {
+        if as_of >= fresh_after {
+            return JsonResponse::success(PredictionResult { weighted, community, aggregator, as_of, stale: false }, StatusCode::OK);
         }
     }
 
-    if count == 0 {
-        return JsonResponse::error("No predictions available yet", StatusCode::UNPROCESSABLE_ENTITY);
-    }
+    let aggregator = match build_aggregator(&state.pool, &state.config.aggregator).await {
+        Some(aggregator) => aggregator,
+        None => return JsonResponse::error("Failed to refresh predictions", StatusCode::INTERNAL_SERVER_ERROR)
+    };
 
-    let weighted = (sum / count as f64 * 10000.0).round() / 10000.0;
+    match fetch_outcome(&state, aggregator.as_ref(), &prediction.prediction_id, &prediction.condition_id).await {
+        Ok(result) => JsonResponse::success(result, StatusCode::OK),
+        Err(FetchOutcomeError::NoPredictions) => JsonResponse::error("No predictions available yet", StatusCode::UNPROCESSABLE_ENTITY),
+        Err(FetchOutcomeError::NoCommunityPrediction) => JsonResponse::error("Failed to fetch community prediction", StatusCode::INTERNAL_SERVER_ERROR),
+        Err(e) => {
+            eprintln!("Error refreshing result for {}: {}", prediction.prediction_id, e);
+            JsonResponse::error("Failed to refresh predictions", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
 
-    let community_url = format!("{}/api/v2/validator/events/{}/community_prediction", &state.config.api_url, prediction.prediction_id);
-    let community_response = match client
-        .get(&community_url)
-        .header("X-API-Key", &state.config.api_key)
-        .send()
+async fn get_prediction_historical(State(state): State<Arc<AppState>>, auth: Auth, Path(id): Path<String>, Query(params): Query<PredictionHistoricalQuery>) -> impl IntoResponse {
+    let prediction = match sqlx::query!(
+        "SELECT p.prediction_id, p.token_id
+        FROM predictions p
+        LEFT JOIN market_outcomes mo ON mo.token_id = p.token_id
+        WHERE p.prediction_id = $1 OR p.condition_id = $1
+        ORDER BY (p.prediction_id = $1) DESC, mo.position
+        LIMIT 1",
+        id)
+        .fetch_optional(&*state.pool)
         .await {
-            Ok(resp) => resp,
-            Err(e) => {
-                eprintln!("Task: API request failed for community prediction for {}: {}", prediction.prediction_id, e);
-                return JsonResponse::error("Failed to fetch community prediction", StatusCode::INTERNAL_SERVER_ERROR);
-            }
+            Ok(Some(pred)) => pred,
+            Ok(None) => return JsonResponse::error("Prediction not found", StatusCode::NOT_FOUND),
+            Err(_) => return JsonResponse::error("Failed to fetch historical data", StatusCode::INTERNAL_SERVER_ERROR)
         };
 
-    if !community_response.status().is_success() {
-        let error = match community_response.text().await {
-            Ok(text) => format!("Task: API request failed for community prediction for {}: {}", prediction.prediction_id, text),
-            Err(_) => "API request failed for community prediction. Error: Unable to read error response".to_string(),
-        };
-        eprintln!("{}", error);
-        return JsonResponse::error("Failed to fetch community prediction", StatusCode::INTERNAL_SERVER_ERROR);
-    }
+    let from = params.from.map(|date| date.naive_utc());
+    let to = params.to.map(|date| date.naive_utc());
 
-    let community = match community_response.json::<Value>().await {
-        Ok(json) => match json.get("community_prediction").and_then(|v| v.as_f64()) {
-            Some(value) => (value * 10000.0).round() / 10000.0,
-            None => return JsonResponse::error("Failed to fetch community prediction", StatusCode::INTERNAL_SERVER_ERROR)
-        },
-        Err(e) => {
-            eprintln!("Task: Error parsing community prediction response for {}: {}", prediction.prediction_id, e);
-            return JsonResponse::error("Failed to fetch community prediction", StatusCode::INTERNAL_SERVER_ERROR);
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return JsonResponse::error("Invalid range", StatusCode::BAD_REQUEST);
         }
+    }
+
+    let bucket = match params.bucket.as_deref() {
+        None | Some("raw") => None,
+        Some("hourly") | Some("hour") => Some("hour"),
+        Some("daily") | Some("day") => Some("day"),
+        Some(_) => return JsonResponse::error("Invalid bucket", StatusCode::BAD_REQUEST)
     };
 
-    let _ = sqlx::query!(
-        "INSERT INTO outcomes (prediction_id, weighted, community, raw) VALUES ($1, $2, $3, $4)",
+    let Some(bucket) = bucket else {
+        let result = sqlx::query_as!(
+            OutcomePoint,
+            r#"SELECT o.created_at AS timestamp, o.weighted, o.community, pr.price AS "price?"
+            FROM outcomes o
+            LEFT JOIN LATERAL (
+                SELECT price FROM prices
+                WHERE token_id = $4 AND created_at <= o.created_at
+                ORDER BY created_at DESC
+                LIMIT 1
+            ) pr ON TRUE
+            WHERE o.prediction_id = $1
+            AND ($2::timestamp IS NULL OR o.created_at >= $2)
+            AND ($3::timestamp IS NULL OR o.created_at <= $3)
+            ORDER BY o.created_at ASC"#,
+            prediction.prediction_id,
+            from,
+            to,
+            prediction.token_id)
+            .fetch_all(&*state.pool)
+            .await;
+
+        return match result {
+            Ok(points) => JsonResponse::success(json!({"prediction_id": prediction.prediction_id, "bucket": "raw", "points": points}), StatusCode::OK),
+            Err(_) => JsonResponse::error("Failed to fetch historical data", StatusCode::INTERNAL_SERVER_ERROR)
+        };
+    };
+
+    let result = sqlx::query_as!(
+        OutcomeBucket,
+        r#"WITH outcome_buckets AS (
+            SELECT date_trunc($4, created_at) AS bucket,
+                COUNT(*) AS count,
+                MIN(weighted) AS weighted_min,
+                MAX(weighted) AS weighted_max,
+                ROUND(AVG(weighted)::numeric, 4)::float8 AS weighted_mean,
+                MIN(community) AS community_min,
+                MAX(community) AS community_max,
+                ROUND(AVG(community)::numeric, 4)::float8 AS community_mean
+            FROM outcomes
+            WHERE prediction_id = $1
+            AND ($2::timestamp IS NULL OR created_at >= $2)
+            AND ($3::timestamp IS NULL OR created_at <= $3)
+            GROUP BY 1
+        ), price_buckets AS (
+            SELECT date_trunc($4, created_at) AS bucket,
+                MIN(price) AS price_min,
+                MAX(price) AS price_max,
+                ROUND(AVG(price)::numeric, 4)::float8 AS price_mean
+            FROM prices
+            WHERE token_id = $5
+            AND ($2::timestamp IS NULL OR created_at >= $2)
+            AND ($3::timestamp IS NULL OR created_at <= $3)
+            GROUP BY 1
+        )
+        SELECT o.bucket AS "timestamp!",
+            o.count AS "count!",
+            o.weighted_min AS "weighted_min!",
+            o.weighted_max AS "weighted_max!",
+            o.weighted_mean AS "weighted_mean!",
+            o.community_min AS "community_min!",
+            o.community_max AS "community_max!",
+            o.community_mean AS "community_mean!",
+            p.price_min AS "price_min?",
+            p.price_max AS "price_max?",
+            p.price_mean AS "price_mean?"
+        FROM outcome_buckets o
+        LEFT JOIN price_buckets p ON p.bucket = o.bucket
+        ORDER BY o.bucket ASC"#,
         prediction.prediction_id,
-        weighted,
-        community,
-        weighted_json)
-        .execute(&*state.pool)
+        from,
+        to,
+        bucket,
+        prediction.token_id)
+        .fetch_all(&*state.pool)
         .await;
 
-    JsonResponse::success(json!({"weighted": weighted,"community": community}), StatusCode::OK)
-}
-
-async fn get_prediction_historical(State(state): State<Arc<AppState>>, auth: Auth, Path(id): Path<String>) -> impl IntoResponse {
-
+    match result {
+        Ok(points) => JsonResponse::success(json!({"prediction_id": prediction.prediction_id, "bucket": bucket, "points": points}), StatusCode::OK),
+        Err(_) => JsonResponse::error("Failed to fetch historical data", StatusCode::INTERNAL_SERVER_ERROR)
+    }
 }
 
 async fn get_prediction_results(State(state): State<Arc<AppState>>, auth: Auth) -> impl IntoResponse {
@@ -307,7 +526,7 @@ async fn get_prediction_results(State(state): State<Arc<AppState>>, auth: Auth)
             FROM outcomes
             ORDER BY prediction_id, created_at DESC
         )
-        SELECT o.prediction_id, p.condition_id, o.weighted, o.community
+        SELECT o.prediction_id, p.condition_id, p.token_id, o.weighted, o.community
         FROM latest_outcomes o
         JOIN predictions p ON o.prediction_id = p.prediction_id
         WHERE p.end_date >= NOW()
@@ -321,39 +540,113 @@ async fn get_prediction_results(State(state): State<Arc<AppState>>, auth: Auth)
     }
 }
 
-async fn get_rates() -> impl IntoResponse {
+async fn get_prediction_edges(State(state): State<Arc<AppState>>, _auth: Auth, Query(params): Query<PredictionEdgesQuery>) -> impl IntoResponse {
+    let use_community = match params.source.as_deref() {
+        None | Some("weighted") => false,
+        Some("community") => true,
+        Some(_) => return JsonResponse::error("Invalid source", StatusCode::BAD_REQUEST)
+    };
+
+    let predictions = match sqlx::query!(
+        r#"WITH latest_outcomes AS (
+            SELECT DISTINCT ON (prediction_id) prediction_id, weighted, community
+            FROM outcomes
+            ORDER BY prediction_id, created_at DESC
+        )
+        SELECT o.prediction_id AS "prediction_id!", o.weighted AS "weighted!", o.community AS "community!",
+            m.condition_id AS "condition_id!", m.question AS "question!", p.token_id AS "token_id!", mo.outcome AS "label?",
+            m.end_date AS "end_date!"
+        FROM latest_outcomes o
+        JOIN predictions p ON o.prediction_id = p.prediction_id
+        JOIN markets m ON p.condition_id = m.condition_id
+        LEFT JOIN market_outcomes mo ON mo.token_id = p.token_id
+        WHERE p.end_date >= NOW() AND p.token_id IS NOT NULL"#)
+        .fetch_all(&*state.pool)
+        .await {
+            Ok(predictions) => predictions,
+            Err(_) => return JsonResponse::error("Failed to fetch edges", StatusCode::INTERNAL_SERVER_ERROR)
+        };
+
+    let token_ids: Vec<String> = predictions.iter()
+        .map(|prediction| prediction.token_id.clone())
+        .collect();
+
+    let prices = match state.polymarket.prices(&token_ids).await {
+        Ok(prices) => prices,
+        Err(e) => {
+            eprintln!("Error fetching prices for edges: {}", e);
+            return JsonResponse::error("Failed to fetch prices", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+    let mut edges = Vec::new();
+
+    for prediction in predictions {
+        let price = match prices.get(&prediction.token_id).and_then(|quote| quote.buy) {
+                Some(price) => price,
+                None => continue,
+            };
+
+        let model = if use_community { prediction.community } else { prediction.weighted };
+        let divergence = ((model - price) * 10000.0).round() / 10000.0;
+        let hours_to_close = ((prediction.end_date - now).num_minutes() as f64 / 60.0 * 100.0).round() / 100.0;
+
+        edges.push(PredictionEdge {
+            prediction_id: prediction.prediction_id,
+            condition_id: prediction.condition_id,
+            token_id: prediction.token_id,
+            label: prediction.label,
+            question: prediction.question,
+            weighted: prediction.weighted,
+            community: prediction.community,
+            price,
+            divergence,
+            abs_divergence: divergence.abs(),
+            direction: if divergence >= 0.0 { "yes".to_string() } else { "no".to_string() },
+            end_date: prediction.end_date,
+            hours_to_close,
+        });
+    }
+
+    edges.sort_by(|a, b| b.abs_divergence.total_cmp(&a.abs_divergence).then(a.end_date.cmp(&b.end_date)));
+
+    JsonResponse::success(edges, StatusCode::OK)
+}
+
+async fn get_rates(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.cache.rates.get_or_fetch("USDC", fetch_rates).await {
+        Ok(rates) => JsonResponse::success(rates, StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error fetching rates: {}", e);
+            JsonResponse::error("Failed to fetch rates", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn fetch_rates() -> Result<Value, String> {
     const CURRENCIES: [&str; 3] = ["BTC", "SOL", "TAO"];
-    
+
     let client = Client::new();
     let url = "https://api.coinbase.com/v2/exchange-rates?currency=USDC";
 
-    match client.get(url).send().await {
-        Ok(response) => {
-            if !response.status().is_success() {
-                return JsonResponse::error("Failed to fetch rates", StatusCode::INTERNAL_SERVER_ERROR);
-            }
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
 
-            match response.json::<Value>().await {
-                Ok(data) => {
-                    if let Some(rates) = data.get("data").and_then(|d| d.get("rates")) {
-                        let mut response = json!({});
-                        
-                        for currency in CURRENCIES.iter() {
-                            if let Some(rate_str) = rates.get(currency).and_then(|v| v.as_str()) {
-                                if let Ok(rate) = rate_str.parse::<f64>() {
-                                    response[currency] = json!(((1.0 / rate) * 100.0).round() / 100.0);
-                                }
-                            }
-                        }
-                        
-                        return JsonResponse::success(response, StatusCode::OK);
-                    }
-
-                    JsonResponse::error("Failed to fetch rates", StatusCode::INTERNAL_SERVER_ERROR)
-                }
-                Err(_) => JsonResponse::error("Failed to parse rates", StatusCode::INTERNAL_SERVER_ERROR)
+    let data = response.json::<Value>().await.map_err(|e| e.to_string())?;
+    let rates = data.get("data").and_then(|d| d.get("rates"))
+        .ok_or_else(|| "missing rates".to_string())?;
+
+    let mut response = json!({});
+    for currency in CURRENCIES.iter() {
+        if let Some(rate_str) = rates.get(currency).and_then(|v| v.as_str()) {
+            if let Ok(rate) = rate_str.parse::<f64>() {
+                response[currency] = json!(((1.0 / rate) * 100.0).round() / 100.0);
             }
         }
-        Err(_) => JsonResponse::error("Failed to fetch rates", StatusCode::INTERNAL_SERVER_ERROR)
     }
+
+    Ok(response)
 }
\ No newline at end of file
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde_json::json;
+
+use crate::prelude::*;
+use crate::utilities::scheduler::{start_run, Trigger};
+use crate::utilities::tasks::jobs;
+
+pub async fn get_cache_stats(State(state): State<Arc<AppState>>, _auth: AdminAuth) -> impl IntoResponse {
+    JsonResponse::success(state.cache.stats().await, StatusCode::OK)
+}
+
+/// Configured jobs with their next scheduled run, followed by the most recent runs (optionally for one job).
+pub async fn get_jobs(State(state): State<Arc<AppState>>, _auth: AdminAuth, Query(params): Query<JobRunsQuery>) -> impl IntoResponse {
+    let limit = match page_size(params.limit) {
+        Some(limit) => limit,
+        None => return JsonResponse::error("Invalid limit", StatusCode::BAD_REQUEST)
+    };
+
+    let last_runs = match sqlx::query_as!(
+        JobRun,
+        "SELECT DISTINCT ON (job) id, job, triggered_by, status, counters, error, started_at, finished_at
+        FROM job_runs
+        ORDER BY job, started_at DESC")
+        .fetch_all(&*state.pool)
+        .await {
+            Ok(runs) => runs,
+            Err(e) => {
+                eprintln!("Error fetching job runs: {}", e);
+                return JsonResponse::error("Failed to fetch jobs", StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    let mut last_runs: HashMap<String, JobRun> = last_runs.into_iter()
+        .map(|run| (run.job.clone(), run))
+        .collect();
+
+    let jobs: Vec<JobResponse> = jobs(&state.config).into_iter()
+        .map(|job| {
+            let last_run = last_runs.remove(job.name);
+            JobResponse {
+                name: job.name.to_string(),
+                schedule: job.schedule.to_string(),
+                next_run: job.schedule.next_run(last_run.as_ref().map(|run| run.started_at)),
+                last_run,
+            }
+        })
+        .collect();
+
+    let runs = match sqlx::query_as!(
+        JobRun,
+        "SELECT id, job, triggered_by, status, counters, error, started_at, finished_at
+        FROM job_runs
+        WHERE ($1::text IS NULL OR job = $1)
+        ORDER BY started_at DESC
+        LIMIT $2",
+        params.job,
+        limit)
+        .fetch_all(&*state.pool)
+        .await {
+            Ok(runs) => runs,
+            Err(e) => {
+                eprintln!("Error fetching job runs: {}", e);
+                return JsonResponse::error("Failed to fetch jobs", StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    JsonResponse::success(json!({"jobs": jobs, "runs": runs}), StatusCode::OK)
+}
+
+/// Starts a job immediately in the background. Fails with 409 if a run of the job is already in progress.
+pub async fn run_job(State(state): State<Arc<AppState>>, _auth: AdminAuth, Path(name): Path<String>) -> impl IntoResponse {
+    let job = match jobs(&state.config).into_iter().find(|job| job.name == name) {
+        Some(job) => job,
+        None => return JsonResponse::error("Job not found", StatusCode::NOT_FOUND)
+    };
+
+    match start_run(&state, &job, Trigger::Manual).await {
+        Ok(Some(run)) => {
+            let run_id = run.id;
+            tokio::spawn(run.execute(Arc::clone(&state)));
+            JsonResponse::success(json!({"job": job.name, "run_id": run_id}), StatusCode::ACCEPTED)
+        }
+        Ok(None) => JsonResponse::error("Job is already running", StatusCode::CONFLICT),
+        Err(e) => {
+            eprintln!("Error starting {}: {}", job.name, e);
+            JsonResponse::error("Failed to start job", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use sqlx::PgPool;
+
+use crate::prelude::*;
+
+pub async fn create_events(State(state): State<Arc<AppState>>, _auth: Auth) -> impl IntoResponse {
+    match ingest_events(&state).await {
+        Ok(report) => JsonResponse::success(report, StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error ingesting events: {}", e);
+            JsonResponse::error("Failed to fetch event data", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_events(State(state): State<Arc<AppState>>, _auth: Auth) -> impl IntoResponse {
+    let events = match sqlx::query_as!(
+        Event,
+        "SELECT e.* FROM events e
+        WHERE EXISTS (
+            SELECT 1 FROM markets m
+            WHERE m.event_id = e.event_id AND m.end_date >= NOW()
+        )
+        ORDER BY e.end_date ASC NULLS LAST, e.event_id ASC")
+        .fetch_all(&*state.pool)
+        .await {
+            Ok(events) => events,
+            Err(e) => {
+                eprintln!("Error fetching events: {}", e);
+                return JsonResponse::error("Failed to fetch events", StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    let event_ids: Vec<String> = events.iter()
+        .map(|event| event.event_id.clone())
+        .collect();
+
+    let mut markets = match event_markets(&state.pool, &event_ids).await {
+        Ok(markets) => markets,
+        Err(e) => {
+            eprintln!("Error fetching event markets: {}", e);
+            return JsonResponse::error("Failed to fetch events", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let response: Vec<EventResponse> = events.into_iter()
+        .map(|event| {
+            let markets = markets.remove(&event.event_id).unwrap_or_default();
+            EventResponse { event, markets }
+        })
+        .collect();
+
+    JsonResponse::success(response, StatusCode::OK)
+}
+
+pub async fn get_event(State(state): State<Arc<AppState>>, _auth: Auth, Path(id): Path<String>) -> impl IntoResponse {
+    let event = match sqlx::query_as!(
+        Event,
+        "SELECT * FROM events WHERE event_id = $1 OR slug = $1 LIMIT 1",
+        id)
+        .fetch_optional(&*state.pool)
+        .await {
+            Ok(Some(event)) => event,
+            Ok(None) => return JsonResponse::error("Event not found", StatusCode::NOT_FOUND),
+            Err(e) => {
+                eprintln!("Error fetching event {}: {}", id, e);
+                return JsonResponse::error("Failed to fetch event", StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    match event_markets(&state.pool, std::slice::from_ref(&event.event_id)).await {
+        Ok(mut markets) => {
+            let markets = markets.remove(&event.event_id).unwrap_or_default();
+            JsonResponse::success(EventResponse { event, markets }, StatusCode::OK)
+        }
+        Err(e) => {
+            eprintln!("Error fetching markets for event {}: {}", id, e);
+            JsonResponse::error("Failed to fetch event", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Markets of each event with the latest stored outcome and price of every prediction, keyed by event.
+async fn event_markets(pool: &PgPool, event_ids: &[String]) -> Result<HashMap<String, Vec<EventMarket>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT m.event_id AS "event_id!", m.condition_id, m.question, m.end_date, m.resolved, m.outcome,
+            p.prediction_id AS "prediction_id?",
+            p.token_id AS "token_id?",
+            mo.outcome AS "label?",
+            o.weighted AS "weighted?",
+            o.community AS "community?",
+            o.created_at AS "as_of?",
+            pr.price AS "price?"
+        FROM markets m
+        LEFT JOIN predictions p ON p.condition_id = m.condition_id
+        LEFT JOIN market_outcomes mo ON mo.token_id = p.token_id
+        LEFT JOIN LATERAL (
+            SELECT weighted, community, created_at FROM outcomes
+            WHERE prediction_id = p.prediction_id
+            ORDER BY created_at DESC
+            LIMIT 1
+        ) o ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT price FROM prices
+            WHERE token_id = p.token_id
+            ORDER BY created_at DESC
+            LIMIT 1
+        ) pr ON TRUE
+        WHERE m.event_id = ANY($1)
+        ORDER BY m.event_id, m.end_date ASC, m.condition_id, mo.position"#,
+        event_ids)
+        .fetch_all(pool)
+        .await?;
+
+    let mut events: HashMap<String, Vec<EventMarket>> = HashMap::new();
+
+    for row in rows {
+        let markets = events.entry(row.event_id).or_default();
+
+        if markets.last().map(|market| market.condition_id != row.condition_id).unwrap_or(true) {
+            markets.push(EventMarket {
+                condition_id: row.condition_id,
+                question: row.question,
+                end_date: row.end_date,
+                resolved: row.resolved,
+                outcome: row.outcome,
+                predictions: Vec::new(),
+            });
+        }
+
+        if let (Some(market), Some(prediction_id)) = (markets.last_mut(), row.prediction_id) {
+            market.predictions.push(EventPrediction {
+                prediction_id,
+                token_id: row.token_id,
+                label: row.label,
+                weighted: row.weighted,
+                community: row.community,
+                price: row.price,
+                as_of: row.as_of,
+            });
+        }
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::prelude::*;
+
+#[derive(Deserialize)]
+pub struct MinerHistoryQuery {
+    limit: Option<i64>,
+}
+
+pub async fn get_miners(State(state): State<Arc<AppState>>, _auth: Auth) -> impl IntoResponse {
+    match miner_summaries(&state.pool, None).await {
+        Ok(miners) => JsonResponse::success(miners, StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error fetching miners: {}", e);
+            JsonResponse::error("Failed to fetch miners", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_miner(State(state): State<Arc<AppState>>, _auth: Auth, Path(id): Path<String>, Query(params): Query<MinerHistoryQuery>) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(500);
+    if limit <= 0 || limit > 5000 {
+        return JsonResponse::error("Invalid limit", StatusCode::BAD_REQUEST);
+    }
+
+    let summary = match miner_summaries(&state.pool, Some(&id)).await {
+        Ok(mut miners) if !miners.is_empty() => miners.remove(0),
+        Ok(_) => return JsonResponse::error("Miner not found", StatusCode::NOT_FOUND),
+        Err(e) => {
+            eprintln!("Error fetching miner {}: {}", id, e);
+            return JsonResponse::error("Failed to fetch miner", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let history = sqlx::query_as!(
+        MinerForecastRecord,
+        "SELECT mp.prediction_id, m.condition_id, m.question, mp.predicted_outcome, mp.created_at, m.resolved, m.outcome
+        FROM miner_predictions mp
+        JOIN predictions p ON p.prediction_id = mp.prediction_id
+        JOIN markets m ON m.condition_id = p.condition_id
+        WHERE mp.miner_id = $1
+        ORDER BY mp.created_at DESC
+        LIMIT $2",
+        id,
+        limit)
+        .fetch_all(&*state.pool)
+        .await;
+
+    match history {
+        Ok(history) => JsonResponse::success(json!({"miner": summary, "history": history}), StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error fetching miner history for {}: {}", id, e);
+            JsonResponse::error("Failed to fetch miner", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Activity and accuracy per miner. Accuracy is scored on each miner's last forecast before the market closed.
+async fn miner_summaries(pool: &PgPool, miner_id: Option<&str>) -> Result<Vec<MinerSummary>, sqlx::Error> {
+    sqlx::query_as!(
+        MinerSummary,
+        r#"WITH totals AS (
+            SELECT COUNT(*) AS total FROM predictions
+        ), activity AS (
+            SELECT miner_id,
+                COUNT(*) AS forecasts,
+                COUNT(DISTINCT prediction_id) AS predictions,
+                MIN(created_at) AS first_seen,
+                MAX(created_at) AS last_seen
+            FROM miner_predictions
+            WHERE $1::text IS NULL OR miner_id = $1
+            GROUP BY miner_id
+        ), final_forecasts AS (
+            SELECT DISTINCT ON (mp.prediction_id, mp.miner_id) mp.miner_id, mp.predicted_outcome,
+                CASE WHEN m.winning_token_id = p.token_id THEN 1.0 ELSE 0.0 END AS outcome
+            FROM miner_predictions mp
+            JOIN predictions p ON p.prediction_id = mp.prediction_id
+            JOIN markets m ON m.condition_id = p.condition_id
+            WHERE m.resolved
+            AND m.winning_token_id IS NOT NULL
+            AND p.token_id IS NOT NULL
+            AND mp.created_at <= m.end_date
+            AND ($1::text IS NULL OR mp.miner_id = $1)
+            ORDER BY mp.prediction_id, mp.miner_id, mp.created_at DESC
+        ), accuracy AS (
+            SELECT miner_id,
+                COUNT(*) AS resolved,
+                AVG(POWER(predicted_outcome - outcome, 2)) AS brier,
+                AVG(CASE WHEN (predicted_outcome >= 0.5) = (outcome = 1.0) THEN 1.0 ELSE 0.0 END) AS hit_rate
+            FROM final_forecasts
+            GROUP BY miner_id
+        )
+        SELECT a.miner_id AS "miner_id!",
+            a.forecasts AS "forecasts!",
+            a.predictions AS "predictions!",
+            ROUND(a.predictions::numeric / NULLIF(t.total, 0), 4)::float8 AS coverage,
+            a.first_seen AS "first_seen!",
+            a.last_seen AS "last_seen!",
+            COALESCE(s.resolved, 0) AS "resolved!",
+            ROUND(s.brier::numeric, 4)::float8 AS brier,
+            ROUND(s.hit_rate, 4)::float8 AS hit_rate
+        FROM activity a
+        CROSS JOIN totals t
+        LEFT JOIN accuracy s ON s.miner_id = a.miner_id
+        ORDER BY s.brier ASC NULLS LAST, a.predictions DESC"#,
+        miner_id)
+        .fetch_all(pool)
+        .await
+}
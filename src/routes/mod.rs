@@ -1,7 +1,17 @@
+pub mod admin;
+pub mod events;
+pub mod miners;
 pub mod oauth;
 pub mod polymarket;
 pub mod solana;
+pub mod stats;
+pub mod stream;
 
+pub use admin::*;
+pub use events::*;
+pub use miners::*;
 pub use oauth::*;
 pub use polymarket::*;
-pub use solana::*;
\ No newline at end of file
+pub use solana::*;
+pub use stats::*;
+pub use stream::*;
\ No newline at end of file
@@ -1,172 +1,21 @@
+use std::collections::HashMap;
 use std::sync::Arc;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use chrono::{DateTime, Utc};
-use reqwest::Client;
 use serde_json::{json, Value};
 
 use crate::prelude::*;
+use crate::utilities::scoring::round;
 
 pub async fn create_markets(State(state): State<Arc<AppState>>, auth: Auth) -> impl IntoResponse {
-    let mut fetched = 0;
-    let mut inserted = 0;
-    let mut next_cursor = "MzUwMDA=".to_string();
-    let mut markets: Vec<Value> = Vec::new();
-    let client = Client::new();
-    let now = Utc::now();
-
-    let allowed_tags = vec![
-        "Crypto", "Memecoins", "Politics", "Geopolitics", "Foreign Policy",
-        "Breaking News", "Elon Musk", "Twitter", "Tech", "Business", "AI"
-    ];
-    let disallowed_tags = vec!["German Election", "Tweet Markets"];
-
-    for _ in 0..50 {
-        println!("Fetching page with next_cursor {}", next_cursor);
-
-        let url = format!("https://clob.polymarket.com/markets?next_cursor={}", next_cursor);
-        let response = match client.get(&url).send().await {
-            Ok(resp) => resp,
-            Err(e) => {
-                eprintln!("Error fetching data: {}", e);
-                return JsonResponse::error("Failed to fetch market data", StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
-
-        let json: Value = match response.json().await {
-            Ok(json) => json,
-            Err(e) => {
-                eprintln!("Error parsing response: {}", e);
-                return JsonResponse::error("Failed to parse market data", StatusCode::BAD_REQUEST);
-            }
-        };
-
-        if !json.get("data").is_some() {
-            break;
-        }
-
-        if let Some(data) = json.get("data").and_then(|v| v.as_array()) {
-            for item in data {
-                markets.push(item.clone());
-            }
-        }
-
-        match json.get("next_cursor").and_then(|v| v.as_str()) {
-            Some(cursor) if cursor != next_cursor => {
-                next_cursor = cursor.to_string();
-            }
-            _ => break,
-        }
-    }
-
-    let markets: Vec<&Value> = markets.iter()
-        .filter(|market| market.get("end_date_iso").is_some())
-        .collect();
-
-    for market in markets {
-        let active = market.get("active").and_then(|v| v.as_bool()).unwrap_or(false);
-        let closed = market.get("closed").and_then(|v| v.as_bool()).unwrap_or(true);
-        let archived = market.get("archived").and_then(|v| v.as_bool()).unwrap_or(true);
-
-        if !active || closed || archived {
-            continue;
-        }
-
-        let condition_id = match market.get("condition_id").and_then(|v| v.as_str()) {
-            Some(id) => id,
-            None => continue,
-        };
-
-        let question = match market.get("question").and_then(|v| v.as_str()) {
-            Some(q) => q,
-            None => continue,
-        };
-
-        let description = market.get("description")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .split_whitespace()
-            .collect::<Vec<&str>>()
-            .join(" ");
-
-        let tags = match market.get("tags").and_then(|v| v.as_array()) {
-            Some(tags_arr) => tags_arr
-                .iter()
-                .filter_map(|t| t.as_str())
-                .collect::<Vec<&str>>(),
-            None => continue,
-        };
-
-        if !tags.iter().any(|tag| allowed_tags.contains(tag)) {
-            continue;
-        }
-
-        if tags.iter().any(|tag| disallowed_tags.contains(tag)) {
-            continue;
-        }
-
-        let tags = format!("[{}]", tags.join(", "));
-
-        let mut yes_token_id = None;
-        let mut no_token_id = None;
-
-        if let Some(tokens) = market.get("tokens").and_then(|v| v.as_array()) {
-            for token in tokens {
-                if let Some(outcome) = token.get("outcome").and_then(|v| v.as_str()) {
-                    if let Some(token_id) = token.get("token_id").and_then(|v| v.as_str()) {
-                        match outcome.to_lowercase().as_str() {
-                            "yes" | "up" => yes_token_id = Some(token_id.to_string()),
-                            "no" | "down" => no_token_id = Some(token_id.to_string()),
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        }
-
-        let end_date = match market.get("end_date_iso").and_then(|v| v.as_str()) {
-            Some(date) => date,
-            None => continue,
-        };
-
-        if let Ok(date) = DateTime::parse_from_rfc3339(end_date) {
-            if date < now {
-                continue;
-            }
-        } else {
-            continue;
-        }
-
-        fetched += 1;
-
-        let result = sqlx::query(
-            "INSERT INTO markets (condition_id, question, description, tags, yes_token_id, no_token_id, end_date, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7::timestamp, NOW())")
-            .bind(&condition_id)
-            .bind(&question)
-            .bind(&description)
-            .bind(&tags)
-            .bind(&yes_token_id)
-            .bind(&no_token_id)
-            .bind(&end_date)
-            .execute(&*state.pool)
-            .await;
-
-        match result {
-            Ok(_) => {
-                inserted += 1;
-            },
-            Err(e) => {
-                if let Some(db_error) = e.as_database_error() {
-                    if db_error.is_unique_violation() {
-                        // Already exists, just continue
-                    }
-                }
-            }
+    match ingest_markets(&state).await {
+        Ok(report) => JsonResponse::success(report, StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error ingesting markets: {}", e);
+            JsonResponse::error("Failed to fetch market data", StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
-
-    JsonResponse::success(format!("{} markets fetched, {} markets inserted", fetched, inserted), StatusCode::OK)
 }
 
 pub async fn get_market_price(State(state): State<Arc<AppState>>, auth: Auth, Path(id): Path<String>) -> impl IntoResponse {
@@ -199,110 +48,253 @@ pub async fn get_market_price(State(state): State<Arc<AppState>>, auth: Auth, Pa
         None => return JsonResponse::error("Market not found", StatusCode::NOT_FOUND)
     };
 
-    let client = Client::new();
-    let body = vec![json!({"token_id": yes_token_id, "side": "BUY"})];
-
-    let response = match client
-        .post("https://clob.polymarket.com/prices")
-        .json(&body)
-        .send()
-        .await {
-            Ok(resp) => resp,
-            Err(e) => {
-                eprintln!("Error fetching price: {}", e);
-                return JsonResponse::error("Failed to fetch price", StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
-
-    if !response.status().is_success() {
-        let error = match response.text().await {
-            Ok(text) => format!("API request failed: {}", text),
-            Err(_) => "API request failed: Unable to read error response".to_string(),
-        };
-        eprintln!("{}", error);
-        return JsonResponse::error("Failed to fetch price", StatusCode::INTERNAL_SERVER_ERROR);
+    if let Some(price) = state.feed.price(&yes_token_id).await {
+        return JsonResponse::success(json!({"condition_id": condition_id, "price": price}), StatusCode::OK);
     }
 
-    let prices_json = match response.json::<Value>().await {
-        Ok(json) => json,
+    let polymarket = Arc::clone(&state.polymarket);
+    let token_id = yes_token_id.clone();
+    let price = state.cache.prices.get_or_fetch(&yes_token_id, move || async move {
+        let prices = polymarket.prices(std::slice::from_ref(&token_id)).await.map_err(|e| e.to_string())?;
+        prices.get(&token_id).and_then(|quote| quote.buy).ok_or_else(|| format!("no quote for {}", token_id))
+    }).await;
+
+    match price {
+        Ok(buy_price) => JsonResponse::success(json!({"condition_id": condition_id, "price": buy_price}), StatusCode::OK),
         Err(e) => {
-            eprintln!("Error parsing price response: {}", e);
-            return JsonResponse::error("Failed to fetch price", StatusCode::INTERNAL_SERVER_ERROR);
+            eprintln!("Error fetching price for {}: {}", condition_id, e);
+            JsonResponse::error("Failed to fetch price", StatusCode::INTERNAL_SERVER_ERROR)
         }
-    };
+    }
+}
 
-    if let Some(token) = prices_json.get(&yes_token_id) {
-        if let Some(buy_price) = token.get("BUY").and_then(|v| v.as_str()) {
-            return JsonResponse::success(json!({"condition_id": condition_id, "price": buy_price}), StatusCode::OK);
+pub async fn get_market_prices(State(state): State<Arc<AppState>>, auth: Auth) -> impl IntoResponse {
+    let source = Arc::clone(&state);
+    match state.cache.market_prices.get_or_fetch("open", move || async move { open_market_prices(&source).await }).await {
+        Ok(prices) => JsonResponse::success(prices, StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error fetching prices: {}", e);
+            JsonResponse::error("Failed to fetch prices", StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
-
-    JsonResponse::error("Failed to fetch price", StatusCode::INTERNAL_SERVER_ERROR)
 }
 
-pub async fn get_market_prices(State(state): State<Arc<AppState>>, auth: Auth) -> impl IntoResponse {
-    let markets = match sqlx::query!(
+/// YES prices of every open market, read from the price feed. Only tokens the feed has not picked up yet (e.g.
+/// markets ingested since its last poll) are quoted upstream.
+async fn open_market_prices(state: &AppState) -> Result<Vec<Value>, String> {
+    let markets = sqlx::query!(
         "SELECT condition_id, yes_token_id FROM markets
         WHERE end_date > NOW() AND yes_token_id IS NOT NULL
         ORDER BY end_date ASC
         LIMIT 500")
         .fetch_all(&*state.pool)
-        .await {
-            Ok(markets) => markets,
-            Err(_) => return JsonResponse::error("Failed to fetch prices", StatusCode::INTERNAL_SERVER_ERROR)
-        };
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut prices = HashMap::new();
+    let mut missing = Vec::new();
+    for token_id in markets.iter().filter_map(|market| market.yes_token_id.as_ref()) {
+        match state.feed.price(token_id).await {
+            Some(price) => { prices.insert(token_id.clone(), price); }
+            None => missing.push(token_id.clone()),
+        }
+    }
 
-    if markets.is_empty() {
-        return JsonResponse::success(Vec::<Value>::new(), StatusCode::OK);
+    if !missing.is_empty() {
+        match state.polymarket.prices(&missing).await {
+            Ok(quotes) => prices.extend(quotes.into_iter().filter_map(|(token_id, quote)| quote.buy.map(|price| (token_id, price)))),
+            Err(e) if prices.is_empty() => return Err(e.to_string()),
+            Err(e) => eprintln!("Error fetching prices: {}", e),
+        }
     }
 
-    let mut body = Vec::new();
+    let mut response = Vec::new();
     for market in &markets {
-        if let Some(token_id) = &market.yes_token_id {
-            body.push(json!({"token_id": token_id, "side": "BUY"}));
+        if let Some(price) = market.yes_token_id.as_ref().and_then(|token_id| prices.get(token_id)) {
+            response.push(json!({"condition_id": market.condition_id, "price": price}));
         }
     }
 
-    let client = Client::new();
-    let response = match client
-        .post("https://clob.polymarket.com/prices")
-        .json(&body)
-        .send()
+    Ok(response)
+}
+
+pub async fn get_market_book(State(state): State<Arc<AppState>>, _auth: Auth, Path(id): Path<String>, Query(params): Query<MarketBookQuery>) -> impl IntoResponse {
+    let depth = params.depth.unwrap_or(5);
+    if depth == 0 || depth > 50 {
+        return JsonResponse::error("Invalid depth", StatusCode::BAD_REQUEST);
+    }
+
+    let outcomes = match sqlx::query!(
+        "SELECT mo.condition_id, mo.token_id, mo.outcome
+        FROM market_outcomes mo
+        WHERE mo.condition_id = (
+            SELECT m.condition_id FROM markets m
+            LEFT JOIN predictions p ON m.condition_id = p.condition_id
+            WHERE m.condition_id = $1 OR p.prediction_id = $1
+            LIMIT 1
+        )
+        ORDER BY mo.position",
+        id)
+        .fetch_all(&*state.pool)
         .await {
-            Ok(resp) => resp,
+            Ok(outcomes) if outcomes.is_empty() => return JsonResponse::error("Market not found", StatusCode::NOT_FOUND),
+            Ok(outcomes) => outcomes,
             Err(e) => {
-                eprintln!("Error fetching prices: {}", e);
-                return JsonResponse::error("Failed to fetch prices", StatusCode::INTERNAL_SERVER_ERROR);
+                eprintln!("Error fetching outcomes for {}: {}", id, e);
+                return JsonResponse::error("Failed to fetch order book", StatusCode::INTERNAL_SERVER_ERROR);
             }
         };
 
-    if !response.status().is_success() {
-        let error = match response.text().await {
-            Ok(text) => format!("API request failed: {}", text),
-            Err(_) => "API request failed: Unable to read error response".to_string(),
-        };
-        eprintln!("{}", error);
-        return JsonResponse::error("Failed to fetch prices", StatusCode::INTERNAL_SERVER_ERROR);
+    let mut books = Vec::new();
+    for outcome in &outcomes {
+        match state.polymarket.book(&outcome.token_id).await {
+            Ok(book) => books.push(book),
+            Err(e) => {
+                eprintln!("Error fetching order book for {}: {}", outcome.token_id, e);
+                return JsonResponse::error("Failed to fetch order book", StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
     }
 
-    let prices_json = match response.json::<Value>().await {
-        Ok(json) => json,
+    let condition_id = outcomes[0].condition_id.clone();
+    let tokens: Vec<TokenBook> = outcomes.into_iter()
+        .zip(books)
+        .map(|(outcome, book)| {
+            let bids: Vec<BookLevel> = book.sorted_bids().into_iter()
+                .take(depth)
+                .map(|level| BookLevel { price: level.price, size: level.size })
+                .collect();
+            let asks: Vec<BookLevel> = book.sorted_asks().into_iter()
+                .take(depth)
+                .map(|level| BookLevel { price: level.price, size: level.size })
+                .collect();
+
+            let bid = bids.first().map(|level| level.price);
+            let ask = asks.first().map(|level| level.price);
+            let (mid, spread) = match (bid, ask) {
+                (Some(bid), Some(ask)) => (Some(round((bid + ask) / 2.0)), Some(round(ask - bid))),
+                _ => (None, None),
+            };
+
+            TokenBook {
+                token_id: outcome.token_id,
+                outcome: outcome.outcome,
+                bid,
+                ask,
+                mid,
+                spread,
+                bid_depth: round(bids.iter().map(|level| level.price * level.size).sum()),
+                ask_depth: round(asks.iter().map(|level| level.price * level.size).sum()),
+                bids,
+                asks,
+            }
+        })
+        .collect();
+
+    JsonResponse::success(json!({"condition_id": condition_id, "depth": depth, "tokens": tokens}), StatusCode::OK)
+}
+
+pub async fn get_market_outcomes(State(state): State<Arc<AppState>>, _auth: Auth, Path(id): Path<String>) -> impl IntoResponse {
+    let condition_id = match sqlx::query_scalar!(
+        "SELECT m.condition_id FROM markets m
+        LEFT JOIN predictions p ON m.condition_id = p.condition_id
+        WHERE m.condition_id = $1 OR p.prediction_id = $1
+        LIMIT 1",
+        id)
+        .fetch_optional(&*state.pool)
+        .await {
+            Ok(Some(condition_id)) => condition_id,
+            Ok(None) => return JsonResponse::error("Market not found", StatusCode::NOT_FOUND),
+            Err(_) => return JsonResponse::error("Failed to fetch outcomes", StatusCode::INTERNAL_SERVER_ERROR)
+        };
+
+    // Prices default to the last stored snapshot and are replaced by live quotes when available.
+    let result = sqlx::query_as!(
+        MarketOutcomeResponse,
+        r#"SELECT mo.token_id, mo.outcome, mo.position, mo.winner,
+            pr.price AS "price?",
+            p.prediction_id AS "prediction_id?",
+            o.weighted AS "weighted?",
+            o.community AS "community?"
+        FROM market_outcomes mo
+        LEFT JOIN predictions p ON p.condition_id = mo.condition_id AND p.token_id = mo.token_id
+        LEFT JOIN LATERAL (
+            SELECT weighted, community FROM outcomes
+            WHERE prediction_id = p.prediction_id
+            ORDER BY created_at DESC
+            LIMIT 1
+        ) o ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT price FROM prices
+            WHERE token_id = mo.token_id
+            ORDER BY created_at DESC
+            LIMIT 1
+        ) pr ON TRUE
+        WHERE mo.condition_id = $1
+        ORDER BY mo.position"#,
+        condition_id)
+        .fetch_all(&*state.pool)
+        .await;
+
+    let mut outcomes = match result {
+        Ok(outcomes) => outcomes,
         Err(e) => {
-            eprintln!("Error parsing prices response: {}", e);
-            return JsonResponse::error("Failed to fetch prices", StatusCode::INTERNAL_SERVER_ERROR);
+            eprintln!("Error fetching outcomes for {}: {}", condition_id, e);
+            return JsonResponse::error("Failed to fetch outcomes", StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
-    let mut response = Vec::new();
-    for market in &markets {
-        if let Some(token_id) = &market.yes_token_id {
-            if let Some(token) = prices_json.get(token_id) {
-                if let Some(buy_price) = token.get("BUY").and_then(|v| v.as_str()) {
-                    response.push(json!({"condition_id": market.condition_id, "price": buy_price}));
+    let token_ids: Vec<String> = outcomes.iter()
+        .map(|outcome| outcome.token_id.clone())
+        .collect();
+
+    match state.polymarket.prices(&token_ids).await {
+        Ok(prices) => {
+            for outcome in &mut outcomes {
+                if let Some(price) = prices.get(&outcome.token_id).and_then(|quote| quote.buy) {
+                    outcome.price = Some(price);
                 }
             }
         }
+        Err(e) => eprintln!("Error fetching live prices for {}: {}", condition_id, e),
+    }
+
+    JsonResponse::success(json!({"condition_id": condition_id, "outcomes": outcomes}), StatusCode::OK)
+}
+
+pub async fn get_market_changes(State(state): State<Arc<AppState>>, _auth: Auth, Path(id): Path<String>) -> impl IntoResponse {
+    let exists = sqlx::query_scalar!(
+        "SELECT condition_id FROM markets WHERE condition_id = $1",
+        id)
+        .fetch_optional(&*state.pool)
+        .await;
+
+    match exists {
+        Ok(Some(_)) => {}
+        Ok(None) => return JsonResponse::error("Market not found", StatusCode::NOT_FOUND),
+        Err(e) => {
+            eprintln!("Error fetching market {}: {}", id, e);
+            return JsonResponse::error("Failed to fetch market changes", StatusCode::INTERNAL_SERVER_ERROR);
+        }
     }
 
-    JsonResponse::success(response, StatusCode::OK)
-}
\ No newline at end of file
+    let changes = sqlx::query_as!(
+        MarketChange,
+        r#"SELECT c.field, c.old_value, c.new_value, c.changed_at,
+            COALESCE(c.changed_at > p.created_at, FALSE) AS "after_prediction!"
+        FROM market_changes c
+        LEFT JOIN predictions p ON p.condition_id = c.condition_id
+        WHERE c.condition_id = $1
+        ORDER BY c.changed_at DESC, c.change_id DESC"#,
+        id)
+        .fetch_all(&*state.pool)
+        .await;
+
+    match changes {
+        Ok(changes) => JsonResponse::success(changes, StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error fetching market changes for {}: {}", id, e);
+            JsonResponse::error("Failed to fetch market changes", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
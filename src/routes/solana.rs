@@ -1,12 +1,14 @@
+use std::str::FromStr;
 use std::sync::Arc;
 use axum::{
-    extract::{Path, State, Json},
+    extract::{Path, Query, State, Json},
     http::StatusCode,
     response::IntoResponse,
 };
 use serde_json::{json, Value};
 use reqwest::Client;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::signature::Signature;
 
 use crate::prelude::*;
 
@@ -67,6 +69,14 @@ pub async fn get_address(State(state): State<Arc<AppState>>, auth: Auth) -> impl
                 }
             };
 
+            let sealed = match state.signer.import(&auth.account_id, &address, &secret).await {
+                Ok(sealed) => sealed,
+                Err(e) => {
+                    eprintln!("Error storing wallet key with {} signer: {}", state.signer.name(), e);
+                    return JsonResponse::error("Failed to create wallet", StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
+
             let subscription = json!({
                 "type": "INCOMING_FUNGIBLE_TX",
                 "attr": {
@@ -116,11 +126,14 @@ pub async fn get_address(State(state): State<Arc<AppState>>, auth: Auth) -> impl
             };
 
             let result = sqlx::query!(
-                "INSERT INTO wallets (account_id, subscription_id, address, secret) VALUES ($1, $2, $3, $4)",
+                "INSERT INTO wallets (account_id, subscription_id, address, secret, secret_key, key_version)
+                VALUES ($1, $2, $3, $4, $5, $6)",
                 auth.account_id,
                 subscription_id,
                 address,
-                secret)
+                sealed.as_ref().map(|sealed| sealed.ciphertext.clone()),
+                sealed.as_ref().map(|sealed| sealed.wrapped_key.clone()),
+                sealed.as_ref().map(|sealed| sealed.key_version))
                 .execute(&*state.pool)
                 .await;
 
@@ -204,17 +217,14 @@ pub async fn create_withdraw(State(state): State<Arc<AppState>>, auth: Auth, Jso
         None => return JsonResponse::error("Invalid address", StatusCode::BAD_REQUEST)
     };
 
-    if address.len() < 32 || address.len() > 44 {
-        return JsonResponse::error("Invalid address", StatusCode::BAD_REQUEST);
-    }
-
     let amount = match payload.get("amount").and_then(|v| v.as_str()) {
         Some(amount) => amount,
         None => return JsonResponse::error("Invalid amount", StatusCode::BAD_REQUEST)
     };
 
-    match amount.parse::<f64>() {
-        Ok(value) if value > 0.0 => value,
+    // "inf" and "NaN" parse as floats; infinity would saturate to u64::MAX below.
+    let amount_usdc = match amount.parse::<f64>().ok().filter(|value| value.is_finite()).map(|value| (value * 1000000.0).round()) {
+        Some(value) if value >= 1.0 => value as u64,
         _ => return JsonResponse::error("Invalid amount", StatusCode::BAD_REQUEST)
     };
 
@@ -227,62 +237,208 @@ pub async fn create_withdraw(State(state): State<Arc<AppState>>, auth: Auth, Jso
             Ok(Some(wallet)) => wallet,
             _ => return JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR)
         };
-    
-    let transaction = json!({
-        "chain": "SOL",
-        "from": wallet.address,
-        "to": address,
-        "amount": amount,
-        "contractAddress": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
-        "digits": 6,
-        "fromPrivateKey": wallet.secret,
-        "feePayer": state.config.solana_gas_address,
-        "feePayerPrivateKey": state.config.solana_gas_secret
-    });
 
-    let client = Client::new();
-    let response = match client
-        .post(&format!("{}/v3/blockchain/token/transaction", state.config.tatum_api_url))
-        .header("X-API-KEY", &state.config.tatum_api_key)
-        .header("Content-Type", "application/json")
-        .json(&transaction)
-        .send()
-        .await {
-            Ok(resp) => resp,
-            Err(e) => {
-                eprintln!("Error making withdrawal request: {}", e);
-                return JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
+    let transaction = match prepare_usdc_transfer(&state, &wallet, address, amount_usdc).await {
+        Ok(transaction) => transaction,
+        Err(TransferError::InvalidAddress(_)) => return JsonResponse::error("Invalid address", StatusCode::BAD_REQUEST),
+        Err(e) => {
+            eprintln!("Error preparing withdrawal from {}: {}", wallet.address, e);
+            return JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
 
-    if !response.status().is_success() {
-        let error = match response.text().await {
-            Ok(text) => format!("API request failed: {}", text),
-            Err(_) => "API request failed: Unable to read error response".to_string()
-        };
-        eprintln!("{}", error);
-        return JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR);
+    let rent = match destination_account_rent(&state, address).await {
+        Ok(rent) => rent,
+        Err(e) => {
+            eprintln!("Error checking withdrawal destination {}: {}", address, e);
+            return JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // Recorded before submitting so a withdrawal that lands but fails to confirm here is still on the books.
+    let signature = transaction.signatures[0].to_string();
+    let mut entries = Vec::new();
+    entries.extend(ledger::transfer(&ledger::wallet_account(&auth.account_id), ledger::EXTERNAL, ledger::USDC, amount_usdc, "principal"));
+    entries.extend(ledger::transfer(ledger::GAS, ledger::NETWORK_FEES, ledger::SOL, network_fee(&transaction), "fee"));
+    if rent > 0 {
+        // The gas wallet funds the destination's new token account, which then belongs to the recipient.
+        entries.extend(ledger::transfer(ledger::GAS, ledger::EXTERNAL, ledger::SOL, rent, "rent"));
     }
 
-    let response_data = match response.json::<Value>().await {
-        Ok(data) => data,
+    let ledger_id = match ledger::record(&state.pool, ledger::NewTransaction {
+        account_id: auth.account_id.clone(),
+        kind: ledger::Kind::Withdrawal,
+        status: ledger::Status::Pending,
+        signature: Some(signature.clone()),
+        external_id: None,
+        entries,
+    }).await {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            eprintln!("Withdrawal {} is already recorded", signature);
+            return JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR);
+        }
         Err(e) => {
-            eprintln!("Error parsing response: {}", e);
+            eprintln!("Error recording withdrawal {}: {}", signature, e);
             return JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
-    match response_data.get("txId").and_then(|v| v.get("txId")).and_then(|v| v.as_str()) {
-        Some(tx_id) => JsonResponse::success(tx_id, StatusCode::CREATED),
-        None => {
-            eprintln!("API request failed: {:?}", response_data);
-            return JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR);
+    let (status, error) = match submit_transaction(&state, &transaction).await {
+        Ok(_) => (ledger::Status::Confirmed, None),
+        Err(TransferError::Rejected(e)) => {
+            eprintln!("Withdrawal {} from {} rejected: {}", signature, wallet.address, e);
+            (ledger::Status::Failed, Some(e))
+        }
+        // Anything else (e.g. a confirmation timeout) may still land, so it stays pending until reconciled.
+        Err(e) => {
+            eprintln!("Error sending withdrawal {} from {}: {}", signature, wallet.address, e);
+            (ledger::Status::Pending, None)
         }
+    };
+
+    if !matches!(status, ledger::Status::Pending) {
+        if let Err(e) = ledger::settle(&state.pool, ledger_id, status, None, error.as_deref(), None).await {
+            eprintln!("Error settling withdrawal {}: {}", signature, e);
+        }
+    }
+
+    match status {
+        ledger::Status::Confirmed => JsonResponse::success(signature, StatusCode::CREATED),
+        ledger::Status::Pending => JsonResponse::success(signature, StatusCode::ACCEPTED),
+        ledger::Status::Failed => JsonResponse::error("Failed to create withdrawal", StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// The account's deposits, withdrawals and swaps, newest first, with their ledger entries.
+pub async fn get_transactions(State(state): State<Arc<AppState>>, auth: Auth, Query(params): Query<TransactionsQuery>) -> impl IntoResponse {
+    let limit = match page_size(params.limit) {
+        Some(limit) => limit,
+        None => return JsonResponse::error("Invalid limit", StatusCode::BAD_REQUEST)
+    };
+
+    if params.kind.as_deref().is_some_and(|kind| !["deposit", "withdrawal", "swap"].contains(&kind)) {
+        return JsonResponse::error("Invalid kind, expected deposit, withdrawal or swap", StatusCode::BAD_REQUEST);
+    }
+
+    if params.status.as_deref().is_some_and(|status| !["pending", "confirmed", "failed"].contains(&status)) {
+        return JsonResponse::error("Invalid status, expected pending, confirmed or failed", StatusCode::BAD_REQUEST);
     }
+
+    let cursor = match params.cursor.as_deref() {
+        Some(cursor) => match decode_cursor::<TransactionCursor>(cursor) {
+            Some(cursor) => Some(cursor),
+            None => return JsonResponse::error("Invalid cursor", StatusCode::BAD_REQUEST)
+        },
+        None => None,
+    };
+
+    let result = sqlx::query_as!(
+        LedgerTransaction,
+        r#"SELECT t.id, t.kind, t.status, t.signature, t.external_id, t.error, t.created_at, t.updated_at,
+            COALESCE(e.entries, '[]') AS "entries!: sqlx::types::Json<Vec<LedgerEntry>>"
+        FROM ledger_transactions t
+        LEFT JOIN LATERAL (
+            SELECT jsonb_agg(jsonb_build_object(
+                'account', account, 'asset', asset, 'decimals', decimals, 'amount', amount, 'leg', leg) ORDER BY id) AS entries
+            FROM ledger_entries
+            WHERE transaction_id = t.id
+        ) e ON TRUE
+        WHERE t.account_id = $1
+        AND ($2::text IS NULL OR t.kind = $2)
+        AND ($3::text IS NULL OR t.status = $3)
+        AND ($4::timestamp IS NULL OR (t.created_at, t.id) < ($4, $5))
+        ORDER BY t.created_at DESC, t.id DESC
+        LIMIT $6"#,
+        auth.account_id,
+        params.kind,
+        params.status,
+        cursor.as_ref().map(|cursor| cursor.created_at),
+        cursor.as_ref().map(|cursor| cursor.id),
+        limit + 1)
+        .fetch_all(&*state.pool)
+        .await;
+
+    let mut transactions = match result {
+        Ok(transactions) => transactions,
+        Err(e) => {
+            eprintln!("Error fetching transactions: {}", e);
+            return JsonResponse::error("Failed to fetch transactions", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let next_cursor = if transactions.len() as i64 > limit {
+        transactions.truncate(limit as usize);
+        transactions.last().map(|transaction| encode_cursor(&TransactionCursor {
+            created_at: transaction.created_at,
+            id: transaction.id,
+        }))
+    } else {
+        None
+    };
+
+    JsonResponse::success_with_meta(transactions, PageMeta { limit, next_cursor }, StatusCode::OK)
 }
 
+/// Records incoming USDC deposits. The notification is only a hint: the amount is read from the confirmed
+/// transaction itself, so a forged notification cannot credit a wallet.
 pub async fn tatum_webhook(State(state): State<Arc<AppState>>, Json(payload): Json<Value>) -> impl IntoResponse {
-    JsonResponse::success(json!(payload), StatusCode::OK)
+    let address = match payload.get("address").and_then(|v| v.as_str()) {
+        Some(address) => address,
+        None => return JsonResponse::error("Invalid notification", StatusCode::BAD_REQUEST)
+    };
+
+    let signature = match payload.get("txId").and_then(|v| v.as_str()).and_then(|v| Signature::from_str(v).ok()) {
+        Some(signature) => signature,
+        None => return JsonResponse::error("Invalid notification", StatusCode::BAD_REQUEST)
+    };
+
+    // Only USDC is tracked
+    if payload.get("asset").and_then(|v| v.as_str()).is_some_and(|asset| asset != state.config.solana_usdc_mint) {
+        return JsonResponse::success(json!({"recorded": false}), StatusCode::OK);
+    }
+
+    let wallet = match sqlx::query_as!(
+        Wallet,
+        "SELECT * FROM wallets WHERE address = $1",
+        address)
+        .fetch_optional(&*state.pool)
+        .await {
+            Ok(Some(wallet)) => wallet,
+            Ok(None) => return JsonResponse::success(json!({"recorded": false}), StatusCode::OK),
+            Err(e) => {
+                eprintln!("Error fetching wallet {}: {}", address, e);
+                return JsonResponse::error("Failed to record deposit", StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    // Unconfirmed transactions get a non-2xx response so the notification is retried.
+    let amount = match token_credit(&state, &signature, &wallet.address, &state.config.solana_usdc_mint).await {
+        Ok(Some(0)) => return JsonResponse::success(json!({"recorded": false}), StatusCode::OK),
+        Ok(Some(amount)) => amount,
+        Ok(None) => return JsonResponse::error("Transaction not confirmed", StatusCode::SERVICE_UNAVAILABLE),
+        Err(e) => {
+            eprintln!("Error fetching deposit {}: {}", signature, e);
+            return JsonResponse::error("Failed to record deposit", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let result = ledger::record(&state.pool, ledger::NewTransaction {
+        account_id: wallet.account_id.clone(),
+        kind: ledger::Kind::Deposit,
+        status: ledger::Status::Confirmed,
+        signature: Some(signature.to_string()),
+        external_id: None,
+        entries: ledger::transfer(ledger::EXTERNAL, &ledger::wallet_account(&wallet.account_id), ledger::USDC, amount, "principal").into(),
+    }).await;
+
+    match result {
+        Ok(id) => JsonResponse::success(json!({"recorded": id.is_some()}), StatusCode::OK),
+        Err(e) => {
+            eprintln!("Error recording deposit {}: {}", signature, e);
+            JsonResponse::error("Failed to record deposit", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
 pub async fn create_swap(State(state): State<Arc<AppState>>, auth: Auth, Json(payload): Json<Value>) -> impl IntoResponse {
@@ -366,35 +522,61 @@ pub async fn create_swap(State(state): State<Arc<AppState>>, auth: Auth, Json(pa
         }
     };
 
-    // Create keypair from private key
-    let private_key_bytes: Vec<u8> = match bs58::decode(&wallet.secret).into_vec() {
-        Ok(bytes) => bytes,
+    // Deserialize the versioned transaction
+    let mut transaction = match bincode::deserialize::<solana_sdk::transaction::VersionedTransaction>(&transaction_bytes) {
+        Ok(tx) => tx,
         Err(e) => {
-            eprintln!("Error decoding private key: {}", e);
+            eprintln!("Error deserializing transaction: {}", e);
             return JsonResponse::error("Failed to create swap", StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
-    let keypair = match solana_sdk::signer::keypair::Keypair::from_bytes(&private_key_bytes) {
-        Ok(kp) => kp,
-        Err(e) => {
-            eprintln!("Error creating keypair: {}", e);
+    // Add the user's signature; the order already carries everything else it needs
+    if let Err(e) = sign_transaction(&*wallet_signer(&state, &wallet), &wallet, &mut transaction).await {
+        eprintln!("Error signing transaction: {}", e);
+        return JsonResponse::error("Failed to create swap", StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    // The transaction id is its first signature. Keep it so a swap whose execute call fails can still be reconciled
+    let transaction_signature = transaction.signatures.first()
+        .filter(|signature| **signature != Signature::default())
+        .map(|signature| signature.to_string());
+
+    // Record the swap at its quoted amounts; the executed amounts replace them once it settles
+    let quoted_out = match token_amount(&order_data, "outAmount") {
+        Some(amount) => amount,
+        None => {
+            eprintln!("No output amount in order response");
             return JsonResponse::error("Failed to create swap", StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
+    let quoted_in = token_amount(&order_data, "inAmount").unwrap_or(amount_usdc);
 
-    // Deserialize the versioned transaction
-    let mut transaction = match bincode::deserialize::<solana_sdk::transaction::VersionedTransaction>(&transaction_bytes) {
-        Ok(tx) => tx,
+    let fee = match (order_data.get("feeMint").and_then(|v| v.as_str()), order_data.get("platformFee").and_then(|fee| token_amount(fee, "amount"))) {
+        (Some(USDC_MINT), Some(amount)) => Some((ledger::USDC, amount)),
+        (Some(CBBTC_MINT), Some(amount)) => Some((ledger::CBBTC, amount)),
+        _ => None,
+    };
+
+    let ledger_id = match ledger::record(&state.pool, ledger::NewTransaction {
+        account_id: auth.account_id.clone(),
+        kind: ledger::Kind::Swap,
+        status: ledger::Status::Pending,
+        signature: transaction_signature,
+        external_id: Some(request_id.to_string()),
+        entries: ledger::swap_entries(&auth.account_id, (ledger::USDC, quoted_in), (ledger::CBBTC, quoted_out), fee),
+    }).await {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            eprintln!("Swap {} is already recorded", request_id);
+            return JsonResponse::error("Failed to create swap", StatusCode::INTERNAL_SERVER_ERROR);
+        }
         Err(e) => {
-            eprintln!("Error deserializing transaction: {}", e);
+            eprintln!("Error recording swap {}: {}", request_id, e);
             return JsonResponse::error("Failed to create swap", StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
-    // Sign the versioned transaction
-    transaction.sign(&[&keypair], solana_sdk::hash::Hash::default());
-
     // Serialize the signed transaction
     let signed_transaction = match bincode::serialize(&transaction) {
         Ok(bytes) => BASE64.encode(bytes),
@@ -441,9 +623,21 @@ pub async fn create_swap(State(state): State<Arc<AppState>>, auth: Auth, Json(pa
         }
     };
 
+    let signature = execute_data.get("signature").and_then(|v| v.as_str());
+
     match execute_data.get("status").and_then(|v| v.as_str()) {
         Some("Success") => {
-            if let Some(signature) = execute_data.get("signature").and_then(|v| v.as_str()) {
+            let entries = ledger::swap_entries(
+                &auth.account_id,
+                (ledger::USDC, token_amount(&execute_data, "inputAmountResult").unwrap_or(quoted_in)),
+                (ledger::CBBTC, token_amount(&execute_data, "outputAmountResult").unwrap_or(quoted_out)),
+                fee);
+
+            if let Err(e) = ledger::settle(&state.pool, ledger_id, ledger::Status::Confirmed, signature, None, Some(entries)).await {
+                eprintln!("Error settling swap {}: {}", request_id, e);
+            }
+
+            if let Some(signature) = signature {
                 JsonResponse::success(signature, StatusCode::CREATED)
             } else {
                 JsonResponse::error("Failed to create swap", StatusCode::INTERNAL_SERVER_ERROR)
@@ -451,8 +645,21 @@ pub async fn create_swap(State(state): State<Arc<AppState>>, auth: Auth, Json(pa
         },
         Some("Failed") => {
             let error = execute_data.get("error").and_then(|v| v.as_str()).unwrap_or("Failed to create swap");
+
+            if let Err(e) = ledger::settle(&state.pool, ledger_id, ledger::Status::Failed, signature, Some(error), None).await {
+                eprintln!("Error settling swap {}: {}", request_id, e);
+            }
+
             JsonResponse::error(error, StatusCode::INTERNAL_SERVER_ERROR)
         },
         _ => JsonResponse::error("Failed to create swap", StatusCode::INTERNAL_SERVER_ERROR)
     }
+}
+
+/// A base-unit token amount, which Jupiter returns as a string.
+fn token_amount(data: &Value, key: &str) -> Option<u64> {
+    match data.get(key)? {
+        Value::String(amount) => amount.parse().ok(),
+        amount => amount.as_u64(),
+    }
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::prelude::*;
+
+#[derive(Deserialize)]
+pub struct CalibrationQuery {
+    tag: Option<String>,
+    hours_before_close: Option<i32>,
+    buckets: Option<usize>,
+}
+
+pub async fn get_calibration(State(state): State<Arc<AppState>>, _auth: Auth, Query(params): Query<CalibrationQuery>) -> impl IntoResponse {
+    let hours_before_close = params.hours_before_close.unwrap_or(0);
+    if hours_before_close < 0 {
+        return JsonResponse::error("Invalid hours_before_close", StatusCode::BAD_REQUEST);
+    }
+
+    let buckets = params.buckets.unwrap_or(10);
+    if buckets == 0 || buckets > 100 {
+        return JsonResponse::error("Invalid buckets", StatusCode::BAD_REQUEST);
+    }
+
+    // Each resolved market is scored on the last forecast and price recorded before the cutoff.
+    let result = sqlx::query!(
+        r#"SELECT p.prediction_id,
+            (m.winning_token_id = p.token_id) AS "resolved_yes!",
+            o.weighted AS "weighted?",
+            o.community AS "community?",
+            pr.price AS "price?"
+        FROM predictions p
+        JOIN markets m ON m.condition_id = p.condition_id
+        LEFT JOIN LATERAL (
+            SELECT weighted, community FROM outcomes
+            WHERE prediction_id = p.prediction_id
+            AND created_at <= m.end_date - make_interval(hours => $2)
+            ORDER BY created_at DESC
+            LIMIT 1
+        ) o ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT price FROM prices
+            WHERE token_id = p.token_id
+            AND created_at <= m.end_date - make_interval(hours => $2)
+            ORDER BY created_at DESC
+            LIMIT 1
+        ) pr ON TRUE
+        WHERE m.resolved
+        AND m.winning_token_id IS NOT NULL
+        AND p.token_id IS NOT NULL
+        AND ($1::text IS NULL OR $1 = ANY(m.tags))"#,
+        params.tag,
+        hours_before_close)
+        .fetch_all(&*state.pool)
+        .await;
+
+    let rows = match result {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Error fetching calibration data: {}", e);
+            return JsonResponse::error("Failed to fetch calibration", StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let mut weighted: Vec<Sample> = Vec::new();
+    let mut community: Vec<Sample> = Vec::new();
+    let mut market: Vec<Sample> = Vec::new();
+
+    for row in &rows {
+        let outcome = if row.resolved_yes { 1.0 } else { 0.0 };
+
+        if let Some(value) = row.weighted {
+            weighted.push((value, outcome));
+        }
+        if let Some(value) = row.community {
+            community.push((value, outcome));
+        }
+        if let Some(value) = row.price {
+            market.push((value, outcome));
+        }
+    }
+
+    let sources = vec![
+        score_source("weighted", &weighted, buckets),
+        score_source("community", &community, buckets),
+        score_source("market", &market, buckets),
+    ];
+
+    JsonResponse::success(json!({
+        "resolved": rows.len(),
+        "tag": params.tag,
+        "hours_before_close": hours_before_close,
+        "sources": sources,
+    }), StatusCode::OK)
+}
@@ -0,0 +1,36 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::IntoResponse;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+use crate::prelude::*;
+
+/// Server-sent events for price changes and newly stored outcomes. `?markets=` takes a comma-separated list of
+/// condition ids; without it every market is streamed. Clients first receive the current price of each token.
+pub async fn get_stream(State(state): State<Arc<AppState>>, _auth: Auth, Query(params): Query<StreamQuery>) -> impl IntoResponse {
+    let markets: Option<HashSet<String>> = params.markets
+        .map(|markets| markets.split(',')
+            .map(|market| market.trim().to_string())
+            .filter(|market| !market.is_empty())
+            .collect())
+        .filter(|markets: &HashSet<String>| !markets.is_empty());
+
+    // Subscribe before taking the snapshot so no update falls between the two.
+    let updates = BroadcastStream::new(state.feed.subscribe());
+    let snapshot = state.feed.snapshot(markets.as_ref()).await;
+
+    let updates = updates.filter_map(move |event| match event {
+        Ok(event) if markets.as_ref().is_none_or(|markets| markets.contains(event.condition_id())) => Some(event),
+        // Lagged subscribers simply miss the dropped updates; the next change for a token carries its latest price.
+        _ => None,
+    });
+
+    let stream = tokio_stream::iter(snapshot)
+        .chain(updates)
+        .map(|event| Event::default().event(event.name()).json_data(&event));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
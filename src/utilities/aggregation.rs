@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use sqlx::PgPool;
+
+use crate::clients::prediction_api::MinerPrediction;
+
+pub const AGGREGATORS: [&str; 5] = ["mean", "median", "trimmed_mean", "log_odds", "accuracy_weighted"];
+
+const EPSILON: f64 = 1e-6;
+
+pub struct MinerForecast {
+    pub miner: Option<String>,
+    pub value: f64,
+}
+
+pub trait Aggregator: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn aggregate(&self, forecasts: &[MinerForecast]) -> Option<f64>;
+}
+
+pub struct Mean;
+
+impl Aggregator for Mean {
+    fn name(&self) -> &'static str {
+        "mean"
+    }
+
+    fn aggregate(&self, forecasts: &[MinerForecast]) -> Option<f64> {
+        if forecasts.is_empty() {
+            return None;
+        }
+
+        Some(forecasts.iter().map(|f| f.value).sum::<f64>() / forecasts.len() as f64)
+    }
+}
+
+pub struct Median;
+
+impl Aggregator for Median {
+    fn name(&self) -> &'static str {
+        "median"
+    }
+
+    fn aggregate(&self, forecasts: &[MinerForecast]) -> Option<f64> {
+        let values = sorted_values(forecasts);
+        if values.is_empty() {
+            return None;
+        }
+
+        let middle = values.len() / 2;
+        if values.len().is_multiple_of(2) {
+            Some((values[middle - 1] + values[middle]) / 2.0)
+        } else {
+            Some(values[middle])
+        }
+    }
+}
+
+/// Drops `fraction` of the forecasts from each tail before averaging.
+pub struct TrimmedMean {
+    pub fraction: f64,
+}
+
+impl Aggregator for TrimmedMean {
+    fn name(&self) -> &'static str {
+        "trimmed_mean"
+    }
+
+    fn aggregate(&self, forecasts: &[MinerForecast]) -> Option<f64> {
+        let values = sorted_values(forecasts);
+        let trim = (values.len() as f64 * self.fraction).floor() as usize;
+        let kept = values.get(trim..values.len().saturating_sub(trim))?;
+
+        if kept.is_empty() {
+            return None;
+        }
+
+        Some(kept.iter().sum::<f64>() / kept.len() as f64)
+    }
+}
+
+/// Averages forecasts in log-odds space, which lets confident miners pull the pool further than a plain mean.
+pub struct LogOddsPool;
+
+impl Aggregator for LogOddsPool {
+    fn name(&self) -> &'static str {
+        "log_odds"
+    }
+
+    fn aggregate(&self, forecasts: &[MinerForecast]) -> Option<f64> {
+        if forecasts.is_empty() {
+            return None;
+        }
+
+        let mean_log_odds = forecasts.iter()
+            .map(|f| {
+                let p = f.value.clamp(EPSILON, 1.0 - EPSILON);
+                (p / (1.0 - p)).ln()
+            })
+            .sum::<f64>() / forecasts.len() as f64;
+
+        Some(1.0 / (1.0 + (-mean_log_odds).exp()))
+    }
+}
+
+/// Weights each miner by its historical skill (1 - Brier score on resolved markets).
+/// Miners without history get the skill of an uninformed 50% forecast.
+pub struct AccuracyWeighted {
+    pub weights: HashMap<String, f64>,
+}
+
+impl AccuracyWeighted {
+    const DEFAULT_WEIGHT: f64 = 0.75;
+}
+
+impl Aggregator for AccuracyWeighted {
+    fn name(&self) -> &'static str {
+        "accuracy_weighted"
+    }
+
+    fn aggregate(&self, forecasts: &[MinerForecast]) -> Option<f64> {
+        let mut sum = 0.0;
+        let mut total_weight = 0.0;
+
+        for forecast in forecasts {
+            let weight = forecast.miner.as_ref()
+                .and_then(|miner| self.weights.get(miner))
+                .copied()
+                .unwrap_or(Self::DEFAULT_WEIGHT);
+
+            sum += forecast.value * weight;
+            total_weight += weight;
+        }
+
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        Some(sum / total_weight)
+    }
+}
+
+pub async fn build_aggregator(pool: &PgPool, name: &str) -> Option<Box<dyn Aggregator>> {
+    match name {
+        "mean" => Some(Box::new(Mean)),
+        "median" => Some(Box::new(Median)),
+        "trimmed_mean" => Some(Box::new(TrimmedMean { fraction: 0.1 })),
+        "log_odds" => Some(Box::new(LogOddsPool)),
+        "accuracy_weighted" => Some(Box::new(AccuracyWeighted { weights: miner_weights(pool).await })),
+        _ => None,
+    }
+}
+
+pub fn miner_forecasts(predictions: &[MinerPrediction]) -> Vec<MinerForecast> {
+    predictions.iter()
+        .filter_map(|prediction| {
+            let value = prediction.predicted_outcome.as_ref()?.as_str()?.parse::<f64>().ok()?;
+            Some(MinerForecast { miner: prediction.miner_id(), value })
+        })
+        .collect()
+}
+
+async fn miner_weights(pool: &PgPool) -> HashMap<String, f64> {
+    let result = sqlx::query!(
+        r#"WITH final_forecasts AS (
+            SELECT DISTINCT ON (mp.prediction_id, mp.miner_id) mp.miner_id, mp.predicted_outcome,
+                CASE WHEN m.winning_token_id = p.token_id THEN 1.0 ELSE 0.0 END AS outcome
+            FROM miner_predictions mp
+            JOIN predictions p ON p.prediction_id = mp.prediction_id
+            JOIN markets m ON m.condition_id = p.condition_id
+            WHERE m.resolved
+            AND m.winning_token_id IS NOT NULL
+            AND p.token_id IS NOT NULL
+            AND mp.created_at <= m.end_date
+            ORDER BY mp.prediction_id, mp.miner_id, mp.created_at DESC
+        )
+        SELECT miner_id, AVG(POWER(predicted_outcome - outcome, 2)) AS "brier!"
+        FROM final_forecasts
+        GROUP BY miner_id"#)
+        .fetch_all(pool)
+        .await;
+
+    match result {
+        Ok(rows) => rows.into_iter()
+            .map(|row| (row.miner_id, (1.0 - row.brier).max(0.0)))
+            .collect(),
+        Err(e) => {
+            eprintln!("Error fetching miner accuracy: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+fn sorted_values(forecasts: &[MinerForecast]) -> Vec<f64> {
+    let mut values: Vec<f64> = forecasts.iter().map(|f| f.value).collect();
+    values.sort_by(|a, b| a.total_cmp(b));
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forecasts(values: &[f64]) -> Vec<MinerForecast> {
+        values.iter().map(|value| MinerForecast { miner: None, value: *value }).collect()
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("expected an aggregate");
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn no_forecasts_aggregate_to_nothing() {
+        let aggregators: Vec<Box<dyn Aggregator>> = vec![
+            Box::new(Mean),
+            Box::new(Median),
+            Box::new(TrimmedMean { fraction: 0.1 }),
+            Box::new(LogOddsPool),
+            Box::new(AccuracyWeighted { weights: HashMap::new() }),
+        ];
+
+        for aggregator in aggregators {
+            assert_eq!(aggregator.aggregate(&[]), None, "{}", aggregator.name());
+        }
+    }
+
+    #[test]
+    fn mean_and_median() {
+        assert_close(Mean.aggregate(&forecasts(&[0.2, 0.4, 0.9])), 0.5);
+        assert_close(Median.aggregate(&forecasts(&[0.9, 0.2, 0.4])), 0.4);
+        assert_close(Median.aggregate(&forecasts(&[0.9, 0.2, 0.4, 0.6])), 0.5);
+    }
+
+    #[test]
+    fn trimmed_mean_drops_both_tails() {
+        let values = forecasts(&[0.0, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 1.0]);
+
+        assert_close(TrimmedMean { fraction: 0.1 }.aggregate(&values), 0.5);
+        // Too few forecasts to trim keeps them all.
+        assert_close(TrimmedMean { fraction: 0.1 }.aggregate(&forecasts(&[0.2, 0.4])), 0.3);
+    }
+
+    #[test]
+    fn log_odds_pool_follows_confident_forecasts() {
+        assert_close(LogOddsPool.aggregate(&forecasts(&[0.5, 0.5])), 0.5);
+        assert_close(LogOddsPool.aggregate(&forecasts(&[0.2, 0.8])), 0.5);
+
+        let pooled = LogOddsPool.aggregate(&forecasts(&[0.5, 0.99])).unwrap();
+        assert!(pooled > Mean.aggregate(&forecasts(&[0.5, 0.99])).unwrap());
+    }
+
+    #[test]
+    fn accuracy_weighted_favours_skilled_miners() {
+        let aggregator = AccuracyWeighted {
+            weights: HashMap::from([("good".to_string(), 0.9), ("bad".to_string(), 0.3), ("useless".to_string(), 0.0)]),
+        };
+        let forecast = |miner: &str, value: f64| MinerForecast { miner: Some(miner.to_string()), value };
+
+        assert_close(aggregator.aggregate(&[forecast("good", 0.8), forecast("bad", 0.4)]), 0.7);
+        // Unknown miners get the default weight.
+        assert_close(aggregator.aggregate(&[forecast("good", 0.8), forecast("new", 0.2)]), 0.87 / 1.65);
+        assert_eq!(aggregator.aggregate(&[forecast("useless", 0.8)]), None);
+    }
+}
@@ -1,6 +1,19 @@
 use serde::Deserialize;
-use std::{sync::Arc, env};
-use sqlx::{Postgres, Pool, PgPool};
+use std::{collections::HashMap, str::FromStr, sync::Arc, env, time::Duration};
+use sqlx::PgPool;
+use sqlx::postgres::PgPoolOptions;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use crate::clients::{GammaClient, PolymarketClient, PredictionApi, RetryPolicy};
+use crate::utilities::aggregation::AGGREGATORS;
+use crate::utilities::cache::{CacheConfig, Caches};
+use crate::utilities::encryption::KeyRing;
+use crate::utilities::signer::{build_signer, Signer, SIGNERS};
+use crate::utilities::feed::PriceFeed;
+use crate::utilities::migrations::run_migrations;
+use crate::utilities::scheduler::Schedule;
+use crate::utilities::tasks::JOB_SCHEDULES;
 use oauth2::{
     basic::BasicClient,
     AuthUrl, TokenUrl, RedirectUrl, ClientId, ClientSecret,
@@ -12,14 +25,60 @@ pub struct Config {
     pub server_port: u16,
     pub base_url: String,
     pub database_url: String,
+    pub database_max_connections: u32,
+    pub migrate_on_start: bool,
     pub google_client_id: String,
     pub google_client_secret: String,
     pub api_key: String,
     pub api_url: String,
+    pub polymarket_url: String,
+    pub gamma_url: String,
     pub tatum_api_key: String,
     pub tatum_api_url: String,
     pub solana_gas_address: String,
     pub solana_gas_secret: String,
+    pub solana_rpc_url: String,
+    pub solana_usdc_mint: String,
+    pub master_keys: MasterKeyConfig,
+    pub signer: String,
+    pub signer_keystore_dir: String,
+    pub signer_socket: String,
+    pub aggregator: String,
+    pub api_timeout_secs: u64,
+    pub api_retries: u32,
+    pub ingest_allowed_tags: Vec<String>,
+    pub ingest_disallowed_tags: Vec<String>,
+    pub ingest_start_cursor: String,
+    pub ingest_page_limit: usize,
+    pub ingest_min_liquidity: f64,
+    pub feed_interval_secs: u64,
+    pub rates_cache: CacheConfig,
+    pub prices_cache: CacheConfig,
+    pub market_prices_cache: CacheConfig,
+    pub result_max_age_minutes: i64,
+    pub job_schedules: HashMap<String, String>,
+    pub admin_account_ids: Vec<String>,
+}
+
+/// Master key settings, loaded on their own by the `signer` and `keys` commands.
+#[derive(Deserialize, Clone)]
+pub struct MasterKeyConfig {
+    pub keys: Vec<String>,
+    pub file: Option<String>,
+    pub version: Option<i32>,
+}
+
+impl MasterKeyConfig {
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+
+        let keys = Config::list_var("MASTER_KEYS", "");
+        let file = env::var("MASTER_KEY_FILE").ok();
+        let version = env::var("MASTER_KEY_VERSION").ok()
+            .map(|version| version.parse::<i32>().expect("MASTER_KEY_VERSION must be a valid number"));
+
+        MasterKeyConfig { keys, file, version }
+    }
 }
 
 impl Config {
@@ -32,28 +91,154 @@ impl Config {
             .expect("SERVER_PORT must be a valid port number");
         let base_url = env::var("BASE_URL").expect("BASE_URL must be set");
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        // Every running job pins one connection for its advisory lock on top of the ones its queries use.
+        let database_max_connections = env::var("DATABASE_MAX_CONNECTIONS").unwrap_or_else(|_| "20".to_string())
+            .parse::<u32>()
+            .expect("DATABASE_MAX_CONNECTIONS must be a valid number");
+        let migrate_on_start = env::var("MIGRATE_ON_START").unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .expect("MIGRATE_ON_START must be true or false");
         let google_client_id = env::var("GOOGLE_CLIENT_ID").expect("GOOGLE_CLIENT_ID must be set");
         let google_client_secret = env::var("GOOGLE_CLIENT_SECRET").expect("GOOGLE_CLIENT_SECRET must be set");
         let api_key = env::var("API_KEY").expect("API_KEY must be set");
         let api_url = env::var("API_URL").expect("API_URL must be set");
+        let polymarket_url = env::var("POLYMARKET_URL").unwrap_or_else(|_| "https://clob.polymarket.com".to_string());
+        let gamma_url = env::var("GAMMA_URL").unwrap_or_else(|_| "https://gamma-api.polymarket.com".to_string());
         let tatum_api_key = env::var("TATUM_API_KEY").expect("TATUM_API_KEY must be set");
         let tatum_api_url = env::var("TATUM_API_URL").expect("TATUM_API_URL must be set");
         let solana_gas_address = env::var("SOLANA_GAS_ADDRESS").expect("SOLANA_GAS_ADDRESS must be set");
         let solana_gas_secret = env::var("SOLANA_GAS_SECRET").expect("SOLANA_GAS_SECRET must be set");
+        let solana_rpc_url = env::var("SOLANA_RPC_URL").unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
+        let solana_usdc_mint = env::var("SOLANA_USDC_MINT").unwrap_or_else(|_| "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string());
+        let master_keys = MasterKeyConfig::from_env();
+        let signer = env::var("SIGNER").unwrap_or_else(|_| "database".to_string());
+        let signer_keystore_dir = Self::signer_keystore_dir_var();
+        let signer_socket = Self::signer_socket_var();
+        let aggregator = env::var("AGGREGATOR").unwrap_or_else(|_| "mean".to_string());
+        let api_timeout_secs = env::var("API_TIMEOUT_SECS").unwrap_or_else(|_| "10".to_string())
+            .parse::<u64>()
+            .expect("API_TIMEOUT_SECS must be a valid number");
+        let api_retries = env::var("API_RETRIES").unwrap_or_else(|_| "3".to_string())
+            .parse::<u32>()
+            .expect("API_RETRIES must be a valid number");
+        let ingest_allowed_tags = Self::list_var("INGEST_ALLOWED_TAGS",
+            "Crypto,Memecoins,Politics,Geopolitics,Foreign Policy,Breaking News,Elon Musk,Twitter,Tech,Business,AI");
+        let ingest_disallowed_tags = Self::list_var("INGEST_DISALLOWED_TAGS", "German Election,Tweet Markets");
+        let ingest_start_cursor = env::var("INGEST_START_CURSOR").unwrap_or_else(|_| "MzUwMDA=".to_string());
+        let ingest_page_limit = env::var("INGEST_PAGE_LIMIT").unwrap_or_else(|_| "50".to_string())
+            .parse::<usize>()
+            .expect("INGEST_PAGE_LIMIT must be a valid number");
+        let ingest_min_liquidity = env::var("INGEST_MIN_LIQUIDITY").unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .expect("INGEST_MIN_LIQUIDITY must be a valid number");
+        let feed_interval_secs = env::var("FEED_INTERVAL_SECS").unwrap_or_else(|_| "15".to_string())
+            .parse::<u64>()
+            .expect("FEED_INTERVAL_SECS must be a valid number");
+        let rates_cache = Self::cache_var("RATES", 60, 300);
+        let prices_cache = Self::cache_var("PRICES", 10, 30);
+        let market_prices_cache = Self::cache_var("MARKET_PRICES", 10, 30);
+        let result_max_age_minutes = env::var("RESULT_MAX_AGE_MINUTES").unwrap_or_else(|_| "90".to_string())
+            .parse::<i64>()
+            .expect("RESULT_MAX_AGE_MINUTES must be a valid number");
+        let job_schedules = JOB_SCHEDULES.iter()
+            .map(|(job, default)| {
+                let key = format!("JOB_{}_SCHEDULE", job.to_uppercase());
+                let schedule = env::var(&key).unwrap_or_else(|_| default.to_string());
+                match Schedule::parse(&schedule) {
+                    Ok(Schedule::After(after)) if !JOB_SCHEDULES.iter().any(|(job, _)| *job == after) => {
+                        panic!("{} must follow a known job, not {}", key, after);
+                    }
+                    Ok(_) => {}
+                    Err(e) => panic!("{} must be a number of seconds, a cron expression, after:<job> or off: {}", key, e),
+                }
+                (job.to_string(), schedule)
+            })
+            .collect();
+        let admin_account_ids = Self::list_var("ADMIN_ACCOUNT_IDS", "");
+
+        if !AGGREGATORS.contains(&aggregator.as_str()) {
+            panic!("AGGREGATOR must be one of: {}", AGGREGATORS.join(", "));
+        }
+
+        if Pubkey::from_str(&solana_usdc_mint).is_err() {
+            panic!("SOLANA_USDC_MINT must be a valid address");
+        }
+
+        if !SIGNERS.contains(&signer.as_str()) {
+            panic!("SIGNER must be one of: {}", SIGNERS.join(", "));
+        }
 
         Config {
             server_ip,
             server_port,
             base_url,
             database_url,
+            database_max_connections,
+            migrate_on_start,
             google_client_id,
             google_client_secret,
             api_key,
             api_url,
+            polymarket_url,
+            gamma_url,
             tatum_api_key,
             tatum_api_url,
             solana_gas_address,
             solana_gas_secret,
+            solana_rpc_url,
+            solana_usdc_mint,
+            master_keys,
+            signer,
+            signer_keystore_dir,
+            signer_socket,
+            aggregator,
+            api_timeout_secs,
+            api_retries,
+            ingest_allowed_tags,
+            ingest_disallowed_tags,
+            ingest_start_cursor,
+            ingest_page_limit,
+            ingest_min_liquidity,
+            feed_interval_secs: feed_interval_secs.max(1),
+            rates_cache,
+            prices_cache,
+            market_prices_cache,
+            result_max_age_minutes,
+            job_schedules,
+            admin_account_ids,
+        }
+    }
+
+    /// Reads `SIGNER_KEYSTORE_DIR`. Also used by the `signer` and `keys` commands, which do not load the full config.
+    pub fn signer_keystore_dir_var() -> String {
+        env::var("SIGNER_KEYSTORE_DIR").unwrap_or_else(|_| "keystore".to_string())
+    }
+
+    /// Reads `SIGNER_SOCKET`.
+    pub fn signer_socket_var() -> String {
+        env::var("SIGNER_SOCKET").unwrap_or_else(|_| "signer.sock".to_string())
+    }
+
+    /// Reads a comma-separated list, falling back to `default` when unset. An empty value yields an empty list.
+    fn list_var(key: &str, default: &str) -> Vec<String> {
+        env::var(key).unwrap_or_else(|_| default.to_string())
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    /// Reads `CACHE_<SOURCE>_TTL_SECS` and `CACHE_<SOURCE>_STALE_SECS`.
+    fn cache_var(source: &str, ttl_secs: u64, stale_secs: u64) -> CacheConfig {
+        let seconds = |suffix: &str, default: u64| {
+            let key = format!("CACHE_{}_{}_SECS", source, suffix);
+            env::var(&key).map(|value| value.parse::<u64>().unwrap_or_else(|_| panic!("{} must be a valid number", key)))
+                .unwrap_or(default)
+        };
+
+        CacheConfig {
+            ttl_secs: seconds("TTL", ttl_secs),
+            stale_secs: seconds("STALE", stale_secs),
         }
     }
 }
@@ -62,6 +247,14 @@ pub struct AppState {
     pub config: Arc<Config>,
     pub pool: Arc<PgPool>,
     pub oauth: Arc<BasicClient>,
+    pub api: Arc<PredictionApi>,
+    pub polymarket: Arc<PolymarketClient>,
+    pub gamma: Arc<GammaClient>,
+    pub solana: Arc<RpcClient>,
+    pub feed: Arc<PriceFeed>,
+    pub cache: Arc<Caches>,
+    pub keys: Arc<KeyRing>,
+    pub signer: Arc<dyn Signer>,
 }
 
 impl AppState {
@@ -69,16 +262,69 @@ impl AppState {
         let config = Arc::new(Config::from_env());
         let pool = Arc::new(Self::establish_connection(&config).await);
         let oauth = Arc::new(Self::create_oauth_client(&config));
+        let api = Arc::new(Self::create_api_client(&config));
+        let polymarket = Arc::new(Self::create_polymarket_client(&config));
+        let gamma = Arc::new(Self::create_gamma_client(&config));
+        let solana = Arc::new(Self::create_solana_client(&config));
+        let feed = Arc::new(PriceFeed::new());
+        let cache = Arc::new(Caches::new(config.rates_cache, config.prices_cache, config.market_prices_cache));
+        let keys = Arc::new(KeyRing::from_config(&config.master_keys).unwrap_or_else(|e| panic!("Failed to load master keys: {}", e)));
+        let signer = build_signer(&config, Arc::clone(&keys)).expect("SIGNER must be valid");
 
         Arc::new(AppState {
             config,
             pool,
             oauth,
+            api,
+            polymarket,
+            gamma,
+            solana,
+            feed,
+            cache,
+            keys,
+            signer,
         })
     }
 
     async fn establish_connection(config: &Config) -> PgPool {
-        Pool::<Postgres>::connect(&config.database_url).await.expect("Failed to connect to the database")
+        let pool = PgPoolOptions::new()
+            .max_connections(config.database_max_connections)
+            .connect(&config.database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        if config.migrate_on_start {
+            run_migrations(&pool).await.expect("Failed to run database migrations");
+        }
+
+        pool
+    }
+
+    fn create_api_client(config: &Config) -> PredictionApi {
+        PredictionApi::new(&config.api_url, &config.api_key, Duration::from_secs(config.api_timeout_secs), Self::retry_policy(config))
+    }
+
+    fn create_polymarket_client(config: &Config) -> PolymarketClient {
+        PolymarketClient::new(&config.polymarket_url, Duration::from_secs(config.api_timeout_secs), Self::retry_policy(config))
+    }
+
+    fn create_gamma_client(config: &Config) -> GammaClient {
+        GammaClient::new(&config.gamma_url, Duration::from_secs(config.api_timeout_secs), Self::retry_policy(config))
+    }
+
+    fn create_solana_client(config: &Config) -> RpcClient {
+        RpcClient::new_with_timeout_and_commitment(
+            config.solana_rpc_url.clone(),
+            Duration::from_secs(config.api_timeout_secs),
+            CommitmentConfig::confirmed())
+    }
+
+    fn retry_policy(config: &Config) -> RetryPolicy {
+        RetryPolicy {
+            // The first attempt is not a retry.
+            attempts: config.api_retries.saturating_add(1),
+            base_delay: Duration::from_millis(500),
+        }
     }
 
     fn create_oauth_client(config: &Config) -> BasicClient {
@@ -48,6 +48,24 @@ impl<S> FromRequestParts<S> for Auth where S: Send + Sync + Deref<Target = AppSt
     }
 }
 
+/// An authenticated account listed in `ADMIN_ACCOUNT_IDS`.
+#[derive(Clone)]
+pub struct AdminAuth;
+
+impl<S> FromRequestParts<S> for AdminAuth where S: Send + Sync + Deref<Target = AppState> {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let auth = Auth::from_request_parts(parts, state).await?;
+
+        if !state.config.admin_account_ids.contains(&auth.account_id) {
+            return Err((StatusCode::FORBIDDEN, JsonResponse::error("Admin access required", StatusCode::FORBIDDEN)).into_response());
+        }
+
+        Ok(AdminAuth)
+    }
+}
+
 async fn get_account_api_key(pool: &sqlx::PgPool, api_key: &str) -> Result<Option<String>, sqlx::Error> {
     let result = sqlx::query_as!(
         Auth,
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{Mutex as AsyncMutex, RwLock};
+
+/// Entries younger than `ttl_secs` are served as-is. Up to `stale_secs` past that they are still served, but trigger
+/// a background refresh. A `ttl_secs` of zero disables caching for the source.
+#[derive(Deserialize, Clone, Copy)]
+pub struct CacheConfig {
+    pub ttl_secs: u64,
+    pub stale_secs: u64,
+}
+
+struct Entry<V> {
+    value: V,
+    stored_at: Instant,
+    refreshing: AtomicBool,
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    stale_hits: AtomicU64,
+    misses: AtomicU64,
+    errors: AtomicU64,
+}
+
+#[derive(Serialize)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub ttl_secs: u64,
+    pub stale_secs: u64,
+    pub entries: usize,
+    pub hits: u64,
+    pub stale_hits: u64,
+    pub misses: u64,
+    pub errors: u64,
+    pub hit_ratio: Option<f64>,
+}
+
+/// Keyed TTL cache with stale-while-revalidate semantics.
+pub struct TtlCache<V> {
+    name: &'static str,
+    config: CacheConfig,
+    entries: RwLock<HashMap<String, Entry<V>>>,
+    /// One lock per key being fetched on a miss, so concurrent misses wait for a single upstream request.
+    in_flight: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+    counters: Counters,
+}
+
+impl<V: Clone + Send + Sync + 'static> TtlCache<V> {
+    pub fn new(name: &'static str, config: CacheConfig) -> Self {
+        TtlCache {
+            name,
+            config,
+            entries: RwLock::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            counters: Counters::default(),
+        }
+    }
+
+    /// Returns the cached value for `key`, calling `fetch` when it is missing or expired. Stale values are returned
+    /// immediately while `fetch` runs in the background; a failed background refresh keeps the stale value.
+    /// Concurrent misses for the same key share one `fetch`.
+    pub async fn get_or_fetch<F, Fut, E>(self: &Arc<Self>, key: &str, fetch: F) -> Result<V, E>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<V, E>> + Send + 'static,
+        E: Display + Send + 'static,
+    {
+        if self.config.ttl_secs == 0 {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return fetch().await;
+        }
+
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        let stale = ttl + Duration::from_secs(self.config.stale_secs);
+
+        {
+            let entries = self.entries.read().await;
+            if let Some(entry) = entries.get(key) {
+                let age = entry.stored_at.elapsed();
+
+                if age < ttl {
+                    self.counters.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(entry.value.clone());
+                }
+
+                if age < stale {
+                    self.counters.stale_hits.fetch_add(1, Ordering::Relaxed);
+                    if !entry.refreshing.swap(true, Ordering::AcqRel) {
+                        self.revalidate(key.to_string(), fetch);
+                    }
+                    return Ok(entry.value.clone());
+                }
+            }
+        }
+
+        let lock = Arc::clone(self.in_flight.lock().unwrap().entry(key.to_string()).or_default());
+        let _guard = lock.lock().await;
+
+        // Another caller may have stored the value while this one waited for the lock.
+        if let Some(entry) = self.entries.read().await.get(key) {
+            if entry.stored_at.elapsed() < ttl {
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(entry.value.clone());
+            }
+        }
+
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        let result = match fetch().await {
+            Ok(value) => {
+                self.insert(key.to_string(), value.clone()).await;
+                Ok(value)
+            }
+            Err(e) => {
+                self.counters.errors.fetch_add(1, Ordering::Relaxed);
+                Err(e)
+            }
+        };
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.get(key).is_some_and(|current| Arc::ptr_eq(current, &lock)) {
+            in_flight.remove(key);
+        }
+
+        result
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        let hits = self.counters.hits.load(Ordering::Relaxed);
+        let stale_hits = self.counters.stale_hits.load(Ordering::Relaxed);
+        let misses = self.counters.misses.load(Ordering::Relaxed);
+        let requests = hits + stale_hits + misses;
+
+        CacheStats {
+            name: self.name,
+            ttl_secs: self.config.ttl_secs,
+            stale_secs: self.config.stale_secs,
+            entries: self.entries.read().await.len(),
+            hits,
+            stale_hits,
+            misses,
+            errors: self.counters.errors.load(Ordering::Relaxed),
+            hit_ratio: (requests > 0).then(|| (hits + stale_hits) as f64 / requests as f64),
+        }
+    }
+
+    fn revalidate<F, Fut, E>(self: &Arc<Self>, key: String, fetch: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<V, E>> + Send + 'static,
+        E: Display + Send + 'static,
+    {
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            match fetch().await {
+                Ok(value) => cache.insert(key, value).await,
+                Err(e) => {
+                    eprintln!("Cache: Error refreshing {} entry {}: {}", cache.name, key, e);
+                    cache.counters.errors.fetch_add(1, Ordering::Relaxed);
+                    if let Some(entry) = cache.entries.read().await.get(&key) {
+                        entry.refreshing.store(false, Ordering::Release);
+                    }
+                }
+            }
+        });
+    }
+
+    async fn insert(&self, key: String, value: V) {
+        let mut entries = self.entries.write().await;
+
+        // Expired entries are only ever replaced, so sweep them here to keep one-off keys from piling up.
+        let expiry = Duration::from_secs(self.config.ttl_secs + self.config.stale_secs);
+        entries.retain(|_, entry| entry.stored_at.elapsed() < expiry);
+
+        entries.insert(key, Entry { value, stored_at: Instant::now(), refreshing: AtomicBool::new(false) });
+    }
+}
+
+/// Caches for upstream data sources, each configured separately.
+pub struct Caches {
+    pub rates: Arc<TtlCache<Value>>,
+    pub prices: Arc<TtlCache<f64>>,
+    pub market_prices: Arc<TtlCache<Vec<Value>>>,
+}
+
+impl Caches {
+    pub fn new(rates: CacheConfig, prices: CacheConfig, market_prices: CacheConfig) -> Self {
+        Caches {
+            rates: Arc::new(TtlCache::new("rates", rates)),
+            prices: Arc::new(TtlCache::new("prices", prices)),
+            market_prices: Arc::new(TtlCache::new("market_prices", market_prices)),
+        }
+    }
+
+    pub async fn stats(&self) -> Vec<CacheStats> {
+        vec![
+            self.rates.stats().await,
+            self.prices.stats().await,
+            self.market_prices.stats().await,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    fn cache(ttl_secs: u64, stale_secs: u64) -> Arc<TtlCache<u64>> {
+        Arc::new(TtlCache::new("test", CacheConfig { ttl_secs, stale_secs }))
+    }
+
+    /// Fetches the call number, counting calls.
+    async fn counted(cache: &Arc<TtlCache<u64>>, calls: &Arc<AtomicUsize>) -> Result<u64, String> {
+        let calls = Arc::clone(calls);
+        cache.get_or_fetch("key", move || async move { Ok(calls.fetch_add(1, Ordering::SeqCst) as u64 + 1) }).await
+    }
+
+    #[tokio::test]
+    async fn fresh_entries_are_served_from_cache() {
+        let (cache, calls) = (cache(60, 0), Arc::new(AtomicUsize::new(0)));
+
+        assert_eq!(counted(&cache, &calls).await, Ok(1));
+        assert_eq!(counted(&cache, &calls).await, Ok(1));
+
+        let stats = cache.stats().await;
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn zero_ttl_disables_caching() {
+        let (cache, calls) = (cache(0, 60), Arc::new(AtomicUsize::new(0)));
+
+        assert_eq!(counted(&cache, &calls).await, Ok(1));
+        assert_eq!(counted(&cache, &calls).await, Ok(2));
+        assert_eq!(cache.stats().await.entries, 0);
+    }
+
+    #[tokio::test]
+    async fn stale_entries_are_served_while_refreshing() {
+        let (cache, calls) = (cache(1, 60), Arc::new(AtomicUsize::new(0)));
+
+        assert_eq!(counted(&cache, &calls).await, Ok(1));
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        assert_eq!(counted(&cache, &calls).await, Ok(1));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(counted(&cache, &calls).await, Ok(2));
+
+        let stats = cache.stats().await;
+        assert_eq!((stats.hits, stats.stale_hits, stats.misses), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_keeps_the_stale_value() {
+        let cache = cache(1, 60);
+
+        assert_eq!(cache.get_or_fetch("key", || async { Ok::<_, String>(1) }).await, Ok(1));
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        assert_eq!(cache.get_or_fetch("key", || async { Err("upstream down".to_string()) }).await, Ok(1));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The failed refresh cleared the flag, so the next stale read refreshes again.
+        assert_eq!(cache.get_or_fetch("key", || async { Ok::<_, String>(2) }).await, Ok(1));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(cache.get_or_fetch("key", || async { Ok::<_, String>(3) }).await, Ok(2));
+        assert_eq!(cache.stats().await.errors, 1);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_fetched_again() {
+        let (cache, calls) = (cache(1, 0), Arc::new(AtomicUsize::new(0)));
+
+        assert_eq!(counted(&cache, &calls).await, Ok(1));
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        assert_eq!(counted(&cache, &calls).await, Ok(2));
+        assert_eq!(cache.stats().await.misses, 2);
+    }
+
+    #[tokio::test]
+    async fn errors_are_not_cached() {
+        let cache = cache(60, 0);
+
+        assert_eq!(cache.get_or_fetch("key", || async { Err::<u64, _>("upstream down".to_string()) }).await, Err("upstream down".to_string()));
+        assert_eq!(cache.get_or_fetch("key", || async { Ok::<_, String>(1) }).await, Ok(1));
+
+        let stats = cache.stats().await;
+        assert_eq!((stats.misses, stats.errors), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn concurrent_misses_share_one_fetch() {
+        let (cache, calls) = (cache(60, 0), Arc::new(AtomicUsize::new(0)));
+
+        let requests: Vec<_> = (0..10).map(|_| {
+            let (cache, calls) = (Arc::clone(&cache), Arc::clone(&calls));
+            tokio::spawn(async move {
+                cache.get_or_fetch("key", move || async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok::<_, String>(calls.fetch_add(1, Ordering::SeqCst) as u64 + 1)
+                }).await
+            })
+        }).collect();
+
+        for request in requests {
+            assert_eq!(request.await.unwrap(), Ok(1));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(cache.in_flight.lock().unwrap().is_empty());
+    }
+}
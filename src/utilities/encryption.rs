@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Pool, Postgres};
+
+use crate::models::Wallet;
+use crate::utilities::app_state::{Config, MasterKeyConfig};
+use crate::utilities::signer::KeystoreSigner;
+
+const NONCE_SIZE: usize = 12;
+
+#[derive(Debug)]
+pub enum EncryptionError {
+    NoMasterKey,
+    InvalidMasterKey(String),
+    UnknownKeyVersion(i32),
+    Malformed,
+    Decrypt,
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncryptionError::NoMasterKey => write!(f, "no master key configured"),
+            EncryptionError::InvalidMasterKey(e) => write!(f, "invalid master key: {}", e),
+            EncryptionError::UnknownKeyVersion(version) => write!(f, "master key version {} is not configured", version),
+            EncryptionError::Malformed => write!(f, "malformed ciphertext"),
+            EncryptionError::Decrypt => write!(f, "decryption failed"),
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+/// A secret encrypted under its own data key, with the data key wrapped by a versioned master key. Both
+/// values are base64 of `nonce || ciphertext`.
+#[derive(Serialize, Deserialize)]
+pub struct SealedSecret {
+    pub ciphertext: String,
+    pub wrapped_key: String,
+    pub key_version: i32,
+}
+
+/// Versioned AES-256-GCM master keys. New secrets are sealed under the active version; older versions are kept
+/// only to open existing records until they are rotated.
+pub struct KeyRing {
+    keys: BTreeMap<i32, Key<Aes256Gcm>>,
+    active: i32,
+}
+
+impl KeyRing {
+    /// Loads `version:base64key` entries from `MASTER_KEYS` and `MASTER_KEY_FILE` (one per line, `#` comments).
+    /// The active version is `MASTER_KEY_VERSION`, or the highest configured version.
+    pub fn from_config(config: &MasterKeyConfig) -> Result<Self, EncryptionError> {
+        let mut entries = config.keys.clone();
+
+        if let Some(path) = &config.file {
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| EncryptionError::InvalidMasterKey(format!("{}: {}", path, e)))?;
+            entries.extend(contents.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string));
+        }
+
+        Self::from_entries(&entries, config.version)
+    }
+
+    /// Parses `version:base64key` entries. The active version is `active`, or the highest configured version.
+    fn from_entries(entries: &[String], active: Option<i32>) -> Result<Self, EncryptionError> {
+        let mut keys = BTreeMap::new();
+        for entry in entries {
+            let (version, key) = entry.split_once(':')
+                .ok_or_else(|| EncryptionError::InvalidMasterKey("expected version:base64key".to_string()))?;
+            let version = version.trim().parse::<i32>()
+                .map_err(|_| EncryptionError::InvalidMasterKey(format!("invalid version {}", version)))?;
+            let bytes = BASE64.decode(key.trim())
+                .map_err(|e| EncryptionError::InvalidMasterKey(format!("version {}: {}", version, e)))?;
+            if bytes.len() != 32 {
+                return Err(EncryptionError::InvalidMasterKey(format!("version {} must be 32 bytes", version)));
+            }
+            keys.insert(version, *Key::<Aes256Gcm>::from_slice(&bytes));
+        }
+
+        let active = match active {
+            Some(version) if keys.contains_key(&version) => version,
+            Some(version) => return Err(EncryptionError::UnknownKeyVersion(version)),
+            None => *keys.keys().next_back().ok_or(EncryptionError::NoMasterKey)?,
+        };
+
+        Ok(KeyRing { keys, active })
+    }
+
+    /// A new random master key, base64-encoded for `MASTER_KEYS` or a keyfile.
+    pub fn generate_key() -> String {
+        BASE64.encode(Aes256Gcm::generate_key(OsRng))
+    }
+
+    pub fn active_version(&self) -> i32 {
+        self.active
+    }
+
+    /// Encrypts `plaintext` under a fresh data key. `context` (e.g. the owning account id) is bound as associated
+    /// data, so a sealed value copied onto another record fails to open.
+    pub fn seal(&self, plaintext: &str, context: &str) -> Result<SealedSecret, EncryptionError> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let ciphertext = encrypt(&data_key, plaintext.as_bytes(), context)?;
+
+        self.wrap(&data_key, ciphertext, context)
+    }
+
+    pub fn open(&self, sealed: &SealedSecret, context: &str) -> Result<String, EncryptionError> {
+        let data_key = self.unwrap_key(sealed, context)?;
+        let plaintext = decrypt(&data_key, &sealed.ciphertext, context)?;
+
+        String::from_utf8(plaintext).map_err(|_| EncryptionError::Malformed)
+    }
+
+    /// Re-wraps the data key under the active master key, leaving the secret's ciphertext untouched.
+    pub fn rewrap(&self, sealed: &SealedSecret, context: &str) -> Result<SealedSecret, EncryptionError> {
+        let data_key = self.unwrap_key(sealed, context)?;
+
+        self.wrap(&data_key, sealed.ciphertext.clone(), context)
+    }
+
+    fn wrap(&self, data_key: &Key<Aes256Gcm>, ciphertext: String, context: &str) -> Result<SealedSecret, EncryptionError> {
+        let master_key = self.keys.get(&self.active).ok_or(EncryptionError::UnknownKeyVersion(self.active))?;
+
+        Ok(SealedSecret {
+            ciphertext,
+            wrapped_key: encrypt(master_key, data_key.as_slice(), context)?,
+            key_version: self.active,
+        })
+    }
+
+    fn unwrap_key(&self, sealed: &SealedSecret, context: &str) -> Result<Key<Aes256Gcm>, EncryptionError> {
+        let master_key = self.keys.get(&sealed.key_version).ok_or(EncryptionError::UnknownKeyVersion(sealed.key_version))?;
+        let data_key = decrypt(master_key, &sealed.wrapped_key, context)?;
+
+        if data_key.len() != 32 {
+            return Err(EncryptionError::Malformed);
+        }
+
+        Ok(*Key::<Aes256Gcm>::from_slice(&data_key))
+    }
+}
+
+fn encrypt(key: &Key<Aes256Gcm>, plaintext: &[u8], context: &str) -> Result<String, EncryptionError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key)
+        .encrypt(&nonce, Payload { msg: plaintext, aad: context.as_bytes() })
+        .map_err(|_| EncryptionError::Malformed)?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+
+    Ok(BASE64.encode(sealed))
+}
+
+fn decrypt(key: &Key<Aes256Gcm>, sealed: &str, context: &str) -> Result<Vec<u8>, EncryptionError> {
+    let sealed = BASE64.decode(sealed).map_err(|_| EncryptionError::Malformed)?;
+    if sealed.len() < NONCE_SIZE {
+        return Err(EncryptionError::Malformed);
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+    Aes256Gcm::new(key)
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: context.as_bytes() })
+        .map_err(|_| EncryptionError::Decrypt)
+}
+
+/// The private key sealed into the wallet's row. Wallets created before encryption (no key version) still hold it
+/// in plaintext until `keys rotate` seals them.
+pub fn wallet_secret(keys: &KeyRing, wallet: &Wallet) -> Result<String, EncryptionError> {
+    let secret = wallet.secret.as_ref().ok_or(EncryptionError::Malformed)?;
+
+    match (wallet.key_version, &wallet.secret_key) {
+        (Some(key_version), Some(wrapped_key)) => keys.open(&SealedSecret {
+            ciphertext: secret.clone(),
+            wrapped_key: wrapped_key.clone(),
+            key_version,
+        }, &wallet.account_id),
+        (None, _) => Ok(secret.clone()),
+        (Some(_), None) => Err(EncryptionError::Malformed),
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct RotationReport {
+    pub sealed: usize,
+    pub rewrapped: usize,
+    pub failed: usize,
+}
+
+/// Seals plaintext wallet secrets and moves every other wallet onto the active master key. With `reencrypt`,
+/// secrets also get fresh data keys instead of only having their existing data key re-wrapped.
+async fn rotate_wallet_keys(pool: &PgPool, keys: &KeyRing, reencrypt: bool) -> Result<RotationReport, sqlx::Error> {
+    let wallets = sqlx::query_as!(
+        Wallet,
+        "SELECT * FROM wallets WHERE secret IS NOT NULL AND (key_version IS DISTINCT FROM $1 OR $2)",
+        keys.active_version(),
+        reencrypt)
+        .fetch_all(pool)
+        .await?;
+
+    let mut report = RotationReport::default();
+
+    for wallet in wallets {
+        let result = match (&wallet.secret, wallet.key_version, &wallet.secret_key) {
+            (Some(secret), Some(key_version), Some(wrapped_key)) if !reencrypt => keys.rewrap(&SealedSecret {
+                ciphertext: secret.clone(),
+                wrapped_key: wrapped_key.clone(),
+                key_version,
+            }, &wallet.account_id),
+            _ => wallet_secret(keys, &wallet).and_then(|secret| keys.seal(&secret, &wallet.account_id)),
+        };
+
+        let sealed = match result {
+            Ok(sealed) => sealed,
+            Err(e) => {
+                eprintln!("Error rotating key for wallet {}: {}", wallet.account_id, e);
+                report.failed += 1;
+                continue;
+            }
+        };
+
+        // Only replace the row if nobody rotated it concurrently.
+        let updated = sqlx::query!(
+            "UPDATE wallets SET secret = $2, secret_key = $3, key_version = $4
+            WHERE account_id = $1 AND secret = $5 AND key_version IS NOT DISTINCT FROM $6",
+            wallet.account_id,
+            sealed.ciphertext,
+            sealed.wrapped_key,
+            sealed.key_version,
+            wallet.secret,
+            wallet.key_version)
+            .execute(pool)
+            .await?;
+
+        match (updated.rows_affected(), wallet.key_version) {
+            (0, _) => report.failed += 1,
+            (_, None) => report.sealed += 1,
+            (_, Some(_)) => report.rewrapped += 1,
+        }
+    }
+
+    Ok(report)
+}
+
+/// Entry point for `predictions-api keys [generate|rotate [--reencrypt]]`. Rotation covers both the wallet rows and
+/// the key files under `SIGNER_KEYSTORE_DIR`, which are sealed under the same master keys.
+pub async fn keys_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first().map(String::as_str) {
+        Some("generate") => {
+            println!("{}", KeyRing::generate_key());
+        }
+        Some("rotate") => {
+            let reencrypt = args.iter().any(|arg| arg == "--reencrypt");
+            let keys = Arc::new(KeyRing::from_config(&MasterKeyConfig::from_env())?);
+            let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+            let pool = Pool::<Postgres>::connect(&database_url).await?;
+
+            let report = rotate_wallet_keys(&pool, &keys, reencrypt).await?;
+            println!("Master key version {}: {} wallets sealed, {} re-wrapped, {} failed",
+                keys.active_version(), report.sealed, report.rewrapped, report.failed);
+
+            let keystore = KeystoreSigner::new(Config::signer_keystore_dir_var(), Arc::clone(&keys));
+            let report = keystore.rotate_keys(reencrypt).await?;
+            println!("Master key version {}: {} keystore files re-wrapped, {} failed",
+                keys.active_version(), report.rewrapped, report.failed);
+        }
+        _ => {
+            eprintln!("Usage: keys generate | keys rotate [--reencrypt]");
+            std::process::exit(2);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(keys: &[(i32, &str)], version: Option<i32>) -> Result<KeyRing, EncryptionError> {
+        let entries: Vec<String> = keys.iter().map(|(version, key)| format!("{}:{}", version, key)).collect();
+        KeyRing::from_entries(&entries, version)
+    }
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let key = KeyRing::generate_key();
+        let keys = ring(&[(1, &key)], None).unwrap();
+
+        let sealed = keys.seal("secret", "account-1").unwrap();
+
+        assert_eq!(sealed.key_version, 1);
+        assert_ne!(sealed.ciphertext, "secret");
+        assert_eq!(keys.open(&sealed, "account-1").unwrap(), "secret");
+    }
+
+    #[test]
+    fn open_fails_under_another_context() {
+        let key = KeyRing::generate_key();
+        let keys = ring(&[(1, &key)], None).unwrap();
+
+        let sealed = keys.seal("secret", "account-1").unwrap();
+
+        assert!(matches!(keys.open(&sealed, "account-2"), Err(EncryptionError::Decrypt)));
+    }
+
+    #[test]
+    fn rewrap_moves_to_the_active_key() {
+        let (old, new) = (KeyRing::generate_key(), KeyRing::generate_key());
+        let sealed = ring(&[(1, &old)], None).unwrap().seal("secret", "account-1").unwrap();
+
+        let keys = ring(&[(1, &old), (2, &new)], None).unwrap();
+        let rewrapped = keys.rewrap(&sealed, "account-1").unwrap();
+
+        assert_eq!(rewrapped.key_version, 2);
+        assert_eq!(rewrapped.ciphertext, sealed.ciphertext);
+        assert_ne!(rewrapped.wrapped_key, sealed.wrapped_key);
+
+        // The old master key is no longer needed to open it.
+        let new_only = ring(&[(2, &new)], None).unwrap();
+        assert_eq!(new_only.open(&rewrapped, "account-1").unwrap(), "secret");
+        assert!(matches!(new_only.open(&sealed, "account-1"), Err(EncryptionError::UnknownKeyVersion(1))));
+    }
+
+    #[test]
+    fn active_version_defaults_to_the_highest() {
+        let (first, second) = (KeyRing::generate_key(), KeyRing::generate_key());
+
+        assert_eq!(ring(&[(2, &second), (1, &first)], None).unwrap().active_version(), 2);
+        assert_eq!(ring(&[(2, &second), (1, &first)], Some(1)).unwrap().active_version(), 1);
+        assert!(matches!(ring(&[(1, &first)], Some(3)), Err(EncryptionError::UnknownKeyVersion(3))));
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        assert!(matches!(ring(&[], None), Err(EncryptionError::NoMasterKey)));
+        assert!(matches!(ring(&[(1, "c2hvcnQ=")], None), Err(EncryptionError::InvalidMasterKey(_))));
+        assert!(matches!(ring(&[(1, "not base64!")], None), Err(EncryptionError::InvalidMasterKey(_))));
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use tokio::sync::{broadcast, RwLock};
+
+use crate::utilities::AppState;
+
+/// Subscribers that fall further behind than this skip ahead instead of blocking the feed.
+const CHANNEL_CAPACITY: usize = 4096;
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeedEvent {
+    Price {
+        condition_id: String,
+        token_id: String,
+        price: f64,
+        timestamp: NaiveDateTime,
+    },
+    Outcome {
+        prediction_id: String,
+        condition_id: String,
+        weighted: f64,
+        community: f64,
+        aggregator: String,
+        created_at: NaiveDateTime,
+    },
+}
+
+impl FeedEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeedEvent::Price { .. } => "price",
+            FeedEvent::Outcome { .. } => "outcome",
+        }
+    }
+
+    pub fn condition_id(&self) -> &str {
+        match self {
+            FeedEvent::Price { condition_id, .. } => condition_id,
+            FeedEvent::Outcome { condition_id, .. } => condition_id,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct PriceSnapshot {
+    condition_id: String,
+    price: f64,
+    timestamp: NaiveDateTime,
+}
+
+/// Latest known price per outcome token, plus a broadcast channel that fans changes out to stream subscribers.
+pub struct PriceFeed {
+    prices: RwLock<HashMap<String, PriceSnapshot>>,
+    sender: broadcast::Sender<FeedEvent>,
+}
+
+impl PriceFeed {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        PriceFeed {
+            prices: RwLock::new(HashMap::new()),
+            sender,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<FeedEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Broadcasts an event. Having no subscribers is not an error.
+    pub fn publish(&self, event: FeedEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub async fn price(&self, token_id: &str) -> Option<f64> {
+        self.prices.read().await.get(token_id).map(|snapshot| snapshot.price)
+    }
+
+    /// Current prices as events, optionally restricted to a set of markets.
+    pub async fn snapshot(&self, condition_ids: Option<&HashSet<String>>) -> Vec<FeedEvent> {
+        self.prices.read().await.iter()
+            .filter(|(_, snapshot)| condition_ids.is_none_or(|ids| ids.contains(&snapshot.condition_id)))
+            .map(|(token_id, snapshot)| FeedEvent::Price {
+                condition_id: snapshot.condition_id.clone(),
+                token_id: token_id.clone(),
+                price: snapshot.price,
+                timestamp: snapshot.timestamp,
+            })
+            .collect()
+    }
+
+    /// Records `(condition_id, token_id, price)` quotes and broadcasts the ones that changed. Returns how many changed.
+    pub async fn update(&self, quotes: Vec<(String, String, f64)>) -> usize {
+        let timestamp = Utc::now().naive_utc();
+        let mut prices = self.prices.write().await;
+        let mut changed = 0;
+
+        for (condition_id, token_id, price) in quotes {
+            if prices.get(&token_id).is_some_and(|snapshot| snapshot.price == price) {
+                continue;
+            }
+
+            prices.insert(token_id.clone(), PriceSnapshot { condition_id: condition_id.clone(), price, timestamp });
+            self.publish(FeedEvent::Price { condition_id, token_id, price, timestamp });
+            changed += 1;
+        }
+
+        changed
+    }
+
+    async fn retain(&self, token_ids: &HashSet<String>) {
+        self.prices.write().await.retain(|token_id, _| token_ids.contains(token_id));
+    }
+
+    async fn clear(&self) {
+        self.prices.write().await.clear();
+    }
+}
+
+/// Polls the CLOB for every open market's outcome tokens on a fixed interval and feeds the results into `state.feed`.
+/// Polling pauses while nobody is streaming; price lookups then fall back to their cached upstream requests.
+pub async fn run_price_feed(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(state.config.feed_interval_secs));
+
+    loop {
+        interval.tick().await;
+
+        if !state.feed.has_subscribers() {
+            // Prices stop updating while paused, so stop serving them.
+            state.feed.clear().await;
+            continue;
+        }
+
+        poll_prices(&state).await;
+    }
+}
+
+async fn poll_prices(state: &AppState) {
+    let tokens = match sqlx::query!(
+        "SELECT mo.condition_id, mo.token_id FROM market_outcomes mo
+        JOIN markets m ON m.condition_id = mo.condition_id
+        WHERE m.end_date > NOW() AND NOT m.resolved")
+        .fetch_all(&*state.pool)
+        .await {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                eprintln!("Feed: Error fetching tokens: {}", e);
+                return;
+            }
+        };
+
+    let token_ids: Vec<String> = tokens.iter().map(|token| token.token_id.clone()).collect();
+
+    let prices = match state.polymarket.prices(&token_ids).await {
+        Ok(prices) => prices,
+        Err(e) => {
+            eprintln!("Feed: Error fetching prices: {}", e);
+            return;
+        }
+    };
+
+    let quotes = tokens.into_iter()
+        .filter_map(|token| {
+            let price = prices.get(&token.token_id).and_then(|quote| quote.buy)?;
+            Some((token.condition_id, token.token_id, price))
+        })
+        .collect();
+
+    state.feed.retain(&token_ids.into_iter().collect()).await;
+    state.feed.update(quotes).await;
+}
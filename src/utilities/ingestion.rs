@@ -0,0 +1,481 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+
+use crate::clients::gamma::Event as GammaEvent;
+use crate::clients::polymarket::Market as PolymarketMarket;
+use crate::clients::ClientError;
+use crate::utilities::app_state::{AppState, Config};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    Inactive,
+    MissingTags,
+    TagNotAllowed,
+    TagDisallowed,
+    InvalidEndDate,
+    Expired,
+    LowLiquidity,
+    BookUnavailable,
+    Unchanged,
+    DatabaseError,
+}
+
+#[derive(Serialize, Default)]
+pub struct IngestionReport {
+    pub pages: usize,
+    pub fetched: usize,
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: BTreeMap<SkipReason, usize>,
+}
+
+/// Gamma returns at most this many events per page.
+const EVENTS_PAGE_SIZE: usize = 100;
+
+#[derive(Serialize, Default)]
+pub struct EventIngestionReport {
+    pub pages: usize,
+    pub fetched: usize,
+    pub stored: usize,
+    pub unmatched: usize,
+    pub linked: u64,
+}
+
+/// Market metadata as we store it, normalised from the CLOB representation.
+struct MarketRecord {
+    condition_id: String,
+    question: String,
+    description: String,
+    tags: Vec<String>,
+    yes_token_id: Option<String>,
+    no_token_id: Option<String>,
+    end_date: NaiveDateTime,
+    /// Every outcome token as `(token_id, label)`, in CLOB order.
+    outcomes: Vec<(String, String)>,
+}
+
+enum UpsertResult {
+    Inserted,
+    Updated,
+    Unchanged,
+}
+
+impl IngestionReport {
+    fn skip(&mut self, reason: SkipReason) {
+        *self.skipped.entry(reason).or_insert(0) += 1;
+    }
+}
+
+/// Pages through the Polymarket CLOB and stores every market that passes the configured filters.
+pub async fn ingest_markets(state: &AppState) -> Result<IngestionReport, ClientError> {
+    let config = &state.config;
+    let mut report = IngestionReport::default();
+    let mut next_cursor = config.ingest_start_cursor.clone();
+    let mut markets: Vec<PolymarketMarket> = Vec::new();
+
+    for _ in 0..config.ingest_page_limit {
+        println!("Ingestion: Fetching page with next_cursor {}", next_cursor);
+
+        let page = state.polymarket.markets(&next_cursor).await?;
+        report.pages += 1;
+        markets.extend(page.data);
+
+        match page.next_cursor {
+            Some(cursor) if cursor != next_cursor => {
+                next_cursor = cursor;
+            }
+            _ => break,
+        }
+    }
+
+    report.fetched = markets.len();
+    let now = Utc::now();
+
+    for market in &markets {
+        let end_date = match check_market(config, market, now) {
+            Ok(end_date) => end_date,
+            Err(reason) => {
+                report.skip(reason);
+                continue;
+            }
+        };
+
+        if config.ingest_min_liquidity > 0.0 {
+            if let Err(reason) = check_liquidity(state, market).await {
+                report.skip(reason);
+                continue;
+            }
+        }
+
+        let record = MarketRecord {
+            condition_id: market.condition_id.clone(),
+            question: market.question.clone(),
+            description: market.description
+                .as_deref()
+                .unwrap_or("")
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" "),
+            tags: market.tags.clone().unwrap_or_default(),
+            yes_token_id: market.yes_token().map(|token| token.token_id.clone()),
+            no_token_id: market.no_token().map(|token| token.token_id.clone()),
+            end_date,
+            outcomes: market.tokens.iter()
+                .map(|token| (token.token_id.clone(), token.outcome.clone()))
+                .collect(),
+        };
+
+        match upsert_market(&state.pool, &record).await {
+            Ok(UpsertResult::Inserted) => report.inserted += 1,
+            Ok(UpsertResult::Updated) => report.updated += 1,
+            Ok(UpsertResult::Unchanged) => report.skip(SkipReason::Unchanged),
+            Err(e) => {
+                eprintln!("Ingestion: Error storing market {}: {}", market.condition_id, e);
+                report.skip(SkipReason::DatabaseError);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Pages through open Gamma events and links each one to the markets we already ingested.
+/// Events that group none of our markets are not stored.
+pub async fn ingest_events(state: &AppState) -> Result<EventIngestionReport, ClientError> {
+    let mut report = EventIngestionReport::default();
+    let mut offset = 0;
+
+    for _ in 0..state.config.ingest_page_limit {
+        let events = state.gamma.events(offset, EVENTS_PAGE_SIZE).await?;
+        report.pages += 1;
+        report.fetched += events.len();
+
+        for event in &events {
+            match store_event(&state.pool, event).await {
+                Ok(Some(linked)) => {
+                    report.stored += 1;
+                    report.linked += linked;
+                }
+                Ok(None) => report.unmatched += 1,
+                Err(e) => eprintln!("Ingestion: Error storing event {}: {}", event.id, e),
+            }
+        }
+
+        if events.len() < EVENTS_PAGE_SIZE {
+            break;
+        }
+
+        offset += events.len();
+    }
+
+    Ok(report)
+}
+
+/// Applies the configured filters, returning the market's end date in UTC when it should be stored.
+fn check_market(config: &Config, market: &PolymarketMarket, now: DateTime<Utc>) -> Result<NaiveDateTime, SkipReason> {
+    if !market.is_open() {
+        return Err(SkipReason::Inactive);
+    }
+
+    let tags = match &market.tags {
+        Some(tags) if !tags.is_empty() => tags,
+        _ => return Err(SkipReason::MissingTags),
+    };
+
+    // An empty allow list admits every tag.
+    if !config.ingest_allowed_tags.is_empty() && !tags.iter().any(|tag| config.ingest_allowed_tags.contains(tag)) {
+        return Err(SkipReason::TagNotAllowed);
+    }
+
+    if tags.iter().any(|tag| config.ingest_disallowed_tags.contains(tag)) {
+        return Err(SkipReason::TagDisallowed);
+    }
+
+    let end_date = market.end_date_iso.as_deref()
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.with_timezone(&Utc))
+        .ok_or(SkipReason::InvalidEndDate)?;
+
+    if end_date < now {
+        return Err(SkipReason::Expired);
+    }
+
+    Ok(end_date.naive_utc())
+}
+
+/// Liquidity is the resting order book depth on the market's YES token.
+async fn check_liquidity(state: &AppState, market: &PolymarketMarket) -> Result<(), SkipReason> {
+    let token = market.yes_token()
+        .or_else(|| market.tokens.first())
+        .ok_or(SkipReason::LowLiquidity)?;
+
+    let book = match state.polymarket.book(&token.token_id).await {
+        Ok(book) => book,
+        Err(e) => {
+            eprintln!("Ingestion: Error fetching order book for {}: {}", market.condition_id, e);
+            return Err(SkipReason::BookUnavailable);
+        }
+    };
+
+    if book.depth() < state.config.ingest_min_liquidity {
+        return Err(SkipReason::LowLiquidity);
+    }
+
+    Ok(())
+}
+
+/// Inserts a new market or updates an existing one, recording every changed field in `market_changes`.
+async fn upsert_market(pool: &PgPool, record: &MarketRecord) -> Result<UpsertResult, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // Concurrent ingestions of a new market race on the insert; the loser finds the row and takes the update path.
+    let inserted = sqlx::query!(
+        "INSERT INTO markets (condition_id, question, description, tags, yes_token_id, no_token_id, end_date, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+        ON CONFLICT (condition_id) DO NOTHING",
+        record.condition_id,
+        record.question,
+        record.description,
+        &record.tags,
+        record.yes_token_id,
+        record.no_token_id,
+        record.end_date)
+        .execute(&mut *tx)
+        .await?;
+
+    if inserted.rows_affected() > 0 {
+        store_market_outcomes(&mut tx, record).await?;
+
+        tx.commit().await?;
+        return Ok(UpsertResult::Inserted);
+    }
+
+    let existing = sqlx::query!(
+        "SELECT question, description, tags, yes_token_id, no_token_id, end_date
+        FROM markets
+        WHERE condition_id = $1
+        FOR UPDATE",
+        record.condition_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let existing_outcomes = sqlx::query_scalar!(
+        "SELECT outcome FROM market_outcomes WHERE condition_id = $1 ORDER BY position",
+        record.condition_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut fields = Vec::new();
+    let mut old_values = Vec::new();
+    let mut new_values = Vec::new();
+
+    let mut diff = |field: &str, old: Option<String>, new: Option<String>| {
+        if old != new {
+            fields.push(field.to_string());
+            old_values.push(old);
+            new_values.push(new);
+        }
+    };
+
+    diff("question", Some(existing.question), Some(record.question.clone()));
+    diff("description", Some(existing.description), Some(record.description.clone()));
+    diff("tags", Some(existing.tags.join(", ")), Some(record.tags.join(", ")));
+    diff("yes_token_id", existing.yes_token_id, record.yes_token_id.clone());
+    diff("no_token_id", existing.no_token_id, record.no_token_id.clone());
+    diff("end_date", Some(format_timestamp(existing.end_date)), Some(format_timestamp(record.end_date)));
+    diff("outcomes", Some(existing_outcomes.join(", ")), Some(record.outcomes.iter().map(|(_, label)| label.as_str()).collect::<Vec<&str>>().join(", ")));
+
+    if fields.is_empty() {
+        return Ok(UpsertResult::Unchanged);
+    }
+
+    let end_date_changed = existing.end_date != record.end_date;
+
+    sqlx::query!(
+        "UPDATE markets
+        SET question = $2, description = $3, tags = $4, yes_token_id = $5, no_token_id = $6, end_date = $7
+        WHERE condition_id = $1",
+        record.condition_id,
+        record.question,
+        record.description,
+        &record.tags,
+        record.yes_token_id,
+        record.no_token_id,
+        record.end_date)
+        .execute(&mut *tx)
+        .await?;
+
+    store_market_outcomes(&mut tx, record).await?;
+
+    // Predictions are tracked until their end date, so an extended market must extend them too.
+    if end_date_changed {
+        sqlx::query!(
+            "UPDATE predictions SET end_date = $2 WHERE condition_id = $1",
+            record.condition_id,
+            record.end_date)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query!(
+        "INSERT INTO market_changes (condition_id, field, old_value, new_value)
+        SELECT $1, field, old_value, new_value
+        FROM UNNEST($2::text[], $3::text[], $4::text[]) AS t(field, old_value, new_value)",
+        record.condition_id,
+        &fields,
+        &old_values as &[Option<String>],
+        &new_values as &[Option<String>])
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    println!("Ingestion: Market {} changed ({})", record.condition_id, fields.join(", "));
+    Ok(UpsertResult::Updated)
+}
+
+/// Replaces the market's outcome tokens, dropping any the CLOB no longer lists.
+async fn store_market_outcomes(conn: &mut PgConnection, record: &MarketRecord) -> Result<(), sqlx::Error> {
+    let (token_ids, labels): (Vec<String>, Vec<String>) = record.outcomes.iter().cloned().unzip();
+
+    sqlx::query!(
+        "DELETE FROM market_outcomes WHERE condition_id = $1 AND NOT (token_id = ANY($2))",
+        record.condition_id,
+        &token_ids)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query!(
+        "INSERT INTO market_outcomes (token_id, condition_id, outcome, position)
+        SELECT token_id, $1, outcome, (position - 1)::int
+        FROM UNNEST($2::text[], $3::text[]) WITH ORDINALITY AS t(token_id, outcome, position)
+        ON CONFLICT (token_id) DO UPDATE
+        SET outcome = EXCLUDED.outcome, position = EXCLUDED.position",
+        record.condition_id,
+        &token_ids,
+        &labels)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+fn format_timestamp(timestamp: NaiveDateTime) -> String {
+    timestamp.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Upserts an event and links its markets, returning how many markets were newly linked,
+/// or `None` when the event contains none of our markets.
+async fn store_event(pool: &PgPool, event: &GammaEvent) -> Result<Option<u64>, sqlx::Error> {
+    let condition_ids: Vec<String> = event.markets.iter()
+        .filter_map(|market| market.condition_id.clone())
+        .collect();
+
+    let mut tx = pool.begin().await?;
+
+    let known = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM markets WHERE condition_id = ANY($1)"#,
+        &condition_ids)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    if known == 0 {
+        return Ok(None);
+    }
+
+    let end_date = event.end_date.as_deref()
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.naive_utc());
+
+    sqlx::query!(
+        "INSERT INTO events (event_id, slug, title, description, end_date, neg_risk)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (event_id) DO UPDATE
+        SET slug = EXCLUDED.slug, title = EXCLUDED.title, description = EXCLUDED.description,
+            end_date = EXCLUDED.end_date, neg_risk = EXCLUDED.neg_risk, updated_at = NOW()",
+        event.id,
+        event.slug,
+        event.title,
+        event.description.as_deref().unwrap_or(""),
+        end_date,
+        event.neg_risk)
+        .execute(&mut *tx)
+        .await?;
+
+    let linked = sqlx::query!(
+        "UPDATE markets SET event_id = $1
+        WHERE condition_id = ANY($2) AND event_id IS DISTINCT FROM $1",
+        event.id,
+        &condition_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    tx.commit().await?;
+
+    Ok(Some(linked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn record(end_day: u32, outcomes: &[(&str, &str)]) -> MarketRecord {
+        MarketRecord {
+            condition_id: "0xabc".to_string(),
+            question: "Who wins?".to_string(),
+            description: "Resolves to the winner.".to_string(),
+            tags: vec!["Politics".to_string()],
+            yes_token_id: None,
+            no_token_id: None,
+            end_date: NaiveDate::from_ymd_opt(2026, 11, end_day).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            outcomes: outcomes.iter().map(|(token_id, label)| (token_id.to_string(), label.to_string())).collect(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn upsert_inserts_then_diffs(pool: PgPool) {
+        let original = record(3, &[("1", "Alice"), ("2", "Bob"), ("3", "Carol")]);
+
+        assert!(matches!(upsert_market(&pool, &original).await.unwrap(), UpsertResult::Inserted));
+        assert!(matches!(upsert_market(&pool, &original).await.unwrap(), UpsertResult::Unchanged));
+
+        let changes: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM market_changes").fetch_one(&pool).await.unwrap();
+        assert_eq!(changes, 0);
+    }
+
+    #[sqlx::test]
+    async fn upsert_records_changes_and_follows_the_market(pool: PgPool) {
+        upsert_market(&pool, &record(3, &[("1", "Alice"), ("2", "Bob"), ("3", "Carol")])).await.unwrap();
+        sqlx::query("INSERT INTO predictions (prediction_id, condition_id, token_id, end_date) VALUES ('p1', '0xabc', '1', '2026-11-03')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Polymarket extends the market and drops Carol.
+        let updated = record(10, &[("1", "Alice"), ("2", "Bob")]);
+        assert!(matches!(upsert_market(&pool, &updated).await.unwrap(), UpsertResult::Updated));
+
+        let fields: Vec<String> = sqlx::query_scalar("SELECT field FROM market_changes ORDER BY change_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(fields, vec!["end_date", "outcomes"]);
+
+        let tokens: Vec<String> = sqlx::query_scalar("SELECT token_id FROM market_outcomes WHERE condition_id = '0xabc' ORDER BY position")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(tokens, vec!["1", "2"]);
+
+        let end_date: NaiveDateTime = sqlx::query_scalar("SELECT end_date FROM predictions WHERE prediction_id = 'p1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(end_date, updated.end_date);
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::fmt;
+use sqlx::PgPool;
+
+#[derive(Clone, Copy)]
+pub struct Asset {
+    pub symbol: &'static str,
+    pub decimals: i16,
+}
+
+pub const USDC: Asset = Asset { symbol: "USDC", decimals: 6 };
+pub const SOL: Asset = Asset { symbol: "SOL", decimals: 9 };
+pub const CBBTC: Asset = Asset { symbol: "CBBTC", decimals: 8 };
+
+/// Counterparties outside the platform.
+pub const EXTERNAL: &str = "external";
+/// The gas wallet that pays network fees for withdrawals.
+pub const GAS: &str = "gas";
+pub const NETWORK_FEES: &str = "network_fees";
+pub const SWAP_FEES: &str = "swap_fees";
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Deposit,
+    Withdrawal,
+    Swap,
+}
+
+impl Kind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Deposit => "deposit",
+            Kind::Withdrawal => "withdrawal",
+            Kind::Swap => "swap",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Pending,
+    Confirmed,
+    Failed,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Pending => "pending",
+            Status::Confirmed => "confirmed",
+            Status::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LedgerError {
+    Unbalanced(&'static str),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::Unbalanced(asset) => write!(f, "{} entries do not balance", asset),
+            LedgerError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for LedgerError {
+    fn from(e: sqlx::Error) -> Self {
+        LedgerError::Database(e)
+    }
+}
+
+pub struct Entry {
+    pub account: String,
+    pub asset: Asset,
+    pub amount: i64,
+    pub leg: &'static str,
+}
+
+pub fn wallet_account(account_id: &str) -> String {
+    format!("wallet:{}", account_id)
+}
+
+/// The debit and credit moving `amount` base units of `asset` from one ledger account to another.
+pub fn transfer(from: &str, to: &str, asset: Asset, amount: u64, leg: &'static str) -> [Entry; 2] {
+    let amount = i64::try_from(amount).unwrap_or(i64::MAX);
+
+    [
+        Entry { account: from.to_string(), asset, amount: -amount, leg },
+        Entry { account: to.to_string(), asset, amount, leg },
+    ]
+}
+
+/// Entries for a swap of `input` for `output`. The wallet nets exactly `-input` and `+output`; a fee charged in
+/// either asset is split out of that leg into `swap_fees`.
+pub fn swap_entries(account_id: &str, input: (Asset, u64), output: (Asset, u64), fee: Option<(Asset, u64)>) -> Vec<Entry> {
+    let wallet = wallet_account(account_id);
+    let (mut paid, mut received) = (input.1, output.1);
+
+    let fee = fee.filter(|(_, amount)| *amount > 0).map(|(asset, amount)| {
+        if asset.symbol == input.0.symbol {
+            paid = paid.saturating_sub(amount);
+        } else {
+            received = received.saturating_add(amount);
+        }
+        transfer(&wallet, SWAP_FEES, asset, amount, "fee")
+    });
+
+    let mut entries = Vec::new();
+    entries.extend(transfer(&wallet, EXTERNAL, input.0, paid, "swap_in"));
+    entries.extend(transfer(EXTERNAL, &wallet, output.0, received, "swap_out"));
+    entries.extend(fee.into_iter().flatten());
+    entries
+}
+
+pub struct NewTransaction {
+    pub account_id: String,
+    pub kind: Kind,
+    pub status: Status,
+    pub signature: Option<String>,
+    pub external_id: Option<String>,
+    pub entries: Vec<Entry>,
+}
+
+fn check_balanced(entries: &[Entry]) -> Result<(), LedgerError> {
+    let mut totals: HashMap<&'static str, i64> = HashMap::new();
+    for entry in entries {
+        *totals.entry(entry.asset.symbol).or_default() += entry.amount;
+    }
+
+    match totals.into_iter().find(|(_, total)| *total != 0) {
+        Some((asset, _)) => Err(LedgerError::Unbalanced(asset)),
+        None => Ok(()),
+    }
+}
+
+async fn insert_entries(conn: &mut sqlx::PgConnection, transaction_id: i64, entries: &[Entry]) -> Result<(), sqlx::Error> {
+    let accounts: Vec<String> = entries.iter().map(|entry| entry.account.clone()).collect();
+    let assets: Vec<String> = entries.iter().map(|entry| entry.asset.symbol.to_string()).collect();
+    let decimals: Vec<i16> = entries.iter().map(|entry| entry.asset.decimals).collect();
+    let amounts: Vec<i64> = entries.iter().map(|entry| entry.amount).collect();
+    let legs: Vec<String> = entries.iter().map(|entry| entry.leg.to_string()).collect();
+
+    sqlx::query!(
+        "INSERT INTO ledger_entries (transaction_id, account, asset, decimals, amount, leg)
+        SELECT $1, * FROM UNNEST($2::text[], $3::text[], $4::smallint[], $5::bigint[], $6::text[])",
+        transaction_id,
+        &accounts,
+        &assets,
+        &decimals,
+        &amounts,
+        &legs)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Records a transaction and its entries. Returns `None` if the account already has a transaction of this kind
+/// with the same signature.
+pub async fn record(pool: &PgPool, transaction: NewTransaction) -> Result<Option<i64>, LedgerError> {
+    check_balanced(&transaction.entries)?;
+
+    let mut tx = pool.begin().await?;
+
+    let id = sqlx::query_scalar!(
+        "INSERT INTO ledger_transactions (account_id, kind, status, signature, external_id)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (account_id, kind, signature) DO NOTHING
+        RETURNING id",
+        transaction.account_id,
+        transaction.kind.as_str(),
+        transaction.status.as_str(),
+        transaction.signature,
+        transaction.external_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let id = match id {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+
+    insert_entries(&mut tx, id, &transaction.entries).await?;
+    tx.commit().await?;
+
+    Ok(Some(id))
+}
+
+/// Moves a pending transaction to its final status. `signature` is kept if already known; `entries`, when given,
+/// replace the recorded ones (e.g. quoted swap amounts with the executed amounts).
+pub async fn settle(pool: &PgPool, id: i64, status: Status, signature: Option<&str>, error: Option<&str>, entries: Option<Vec<Entry>>) -> Result<(), LedgerError> {
+    if let Some(entries) = &entries {
+        check_balanced(entries)?;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query!(
+        "UPDATE ledger_transactions SET status = $2, signature = COALESCE($3, signature), error = $4, updated_at = NOW()
+        WHERE id = $1 AND status = 'pending'",
+        id,
+        status.as_str(),
+        signature,
+        error)
+        .execute(&mut *tx)
+        .await?;
+
+    if updated.rows_affected() > 0 {
+        if let Some(entries) = entries {
+            sqlx::query!("DELETE FROM ledger_entries WHERE transaction_id = $1", id)
+                .execute(&mut *tx)
+                .await?;
+            insert_entries(&mut tx, id, &entries).await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(entries: &[Entry], account: &str, asset: Asset) -> i64 {
+        entries.iter()
+            .filter(|entry| entry.account == account && entry.asset.symbol == asset.symbol)
+            .map(|entry| entry.amount)
+            .sum()
+    }
+
+    #[test]
+    fn swap_without_fee_moves_both_assets() {
+        let entries = swap_entries("alice", (USDC, 100_000_000), (CBBTC, 150_000), None);
+
+        assert!(check_balanced(&entries).is_ok());
+        assert_eq!(entries.len(), 4);
+        assert_eq!(net(&entries, "wallet:alice", USDC), -100_000_000);
+        assert_eq!(net(&entries, "wallet:alice", CBBTC), 150_000);
+        assert_eq!(net(&entries, SWAP_FEES, USDC), 0);
+    }
+
+    #[test]
+    fn input_fee_is_split_out_of_the_input_leg() {
+        let entries = swap_entries("alice", (USDC, 100_000_000), (CBBTC, 150_000), Some((USDC, 100_000)));
+
+        assert!(check_balanced(&entries).is_ok());
+        assert_eq!(net(&entries, "wallet:alice", USDC), -100_000_000);
+        assert_eq!(net(&entries, EXTERNAL, USDC), 99_900_000);
+        assert_eq!(net(&entries, SWAP_FEES, USDC), 100_000);
+        assert_eq!(net(&entries, "wallet:alice", CBBTC), 150_000);
+    }
+
+    #[test]
+    fn output_fee_is_added_to_the_output_leg() {
+        let entries = swap_entries("alice", (USDC, 100_000_000), (CBBTC, 150_000), Some((CBBTC, 150)));
+
+        assert!(check_balanced(&entries).is_ok());
+        assert_eq!(net(&entries, "wallet:alice", CBBTC), 150_000);
+        assert_eq!(net(&entries, EXTERNAL, CBBTC), -150_150);
+        assert_eq!(net(&entries, SWAP_FEES, CBBTC), 150);
+    }
+
+    #[test]
+    fn zero_fee_adds_no_entries() {
+        let entries = swap_entries("alice", (USDC, 1_000_000), (CBBTC, 1_500), Some((USDC, 0)));
+
+        assert_eq!(entries.len(), 4);
+        assert!(entries.iter().all(|entry| entry.account != SWAP_FEES));
+    }
+
+    #[test]
+    fn unbalanced_entries_are_rejected() {
+        let mut entries = Vec::from(transfer(GAS, NETWORK_FEES, SOL, 5000, "fee"));
+        entries.push(Entry { account: GAS.to_string(), asset: SOL, amount: -1, leg: "fee" });
+
+        assert!(matches!(check_balanced(&entries), Err(LedgerError::Unbalanced("SOL"))));
+    }
+
+    #[test]
+    fn assets_balance_independently() {
+        let entries = vec![
+            Entry { account: "wallet:alice".to_string(), asset: USDC, amount: -10, leg: "swap_in" },
+            Entry { account: EXTERNAL.to_string(), asset: CBBTC, amount: 10, leg: "swap_out" },
+        ];
+
+        assert!(check_balanced(&entries).is_err());
+    }
+}
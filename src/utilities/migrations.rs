@@ -0,0 +1,62 @@
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::{PgPool, Pool, Postgres};
+
+/// Migrations under `migrations/`, embedded at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+pub async fn run_migrations(pool: &PgPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+/// Reverts the most recently applied migration. Returns its version, or `None` if nothing was applied.
+pub async fn revert_migration(pool: &PgPool) -> Result<Option<i64>, MigrateError> {
+    let versions: Vec<i64> = sqlx::query_scalar(
+        "SELECT version FROM _sqlx_migrations WHERE success ORDER BY version DESC LIMIT 2")
+        .fetch_all(pool)
+        .await?;
+
+    let latest = match versions.first() {
+        Some(latest) => *latest,
+        None => return Ok(None),
+    };
+
+    MIGRATOR.undo(pool, versions.get(1).copied().unwrap_or(0)).await?;
+
+    Ok(Some(latest))
+}
+
+/// Entry point for `predictions-api migrate [run|revert|status]`. Only needs `DATABASE_URL`.
+pub async fn migrate_command(args: &[String]) -> Result<(), MigrateError> {
+    dotenv::dotenv().ok();
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = Pool::<Postgres>::connect(&database_url).await?;
+
+    match args.first().map(String::as_str).unwrap_or("run") {
+        "run" => {
+            run_migrations(&pool).await?;
+            println!("Migrations applied");
+        }
+        "revert" => match revert_migration(&pool).await? {
+            Some(version) => println!("Reverted migration {}", version),
+            None => println!("No migrations to revert"),
+        },
+        "status" => {
+            let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+                .fetch_all(&pool)
+                .await
+                .unwrap_or_default();
+
+            for migration in MIGRATOR.iter().filter(|migration| migration.migration_type.is_up_migration()) {
+                let state = if applied.contains(&migration.version) { "applied" } else { "pending" };
+                println!("{} {} ({})", migration.version, migration.description, state);
+            }
+        }
+        command => {
+            eprintln!("Unknown migrate command: {} (expected run, revert or status)", command);
+            std::process::exit(2);
+        }
+    }
+
+    Ok(())
+}
@@ -2,8 +2,31 @@ pub mod app_state;
 pub mod response;
 pub mod auth;
 pub mod tasks;
+pub mod scoring;
+pub mod aggregation;
+pub mod outcomes;
+pub mod ingestion;
+pub mod pagination;
+pub mod predictions;
+pub mod feed;
+pub mod cache;
+pub mod scheduler;
+pub mod migrations;
+pub mod encryption;
+pub mod signer;
+pub mod transfers;
+pub mod ledger;
 
 pub use app_state::{AppState, Config};
 pub use response::JsonResponse;
-pub use auth::{Auth, generate_session_id};
-pub use tasks::*;
\ No newline at end of file
+pub use auth::{AdminAuth, Auth, generate_session_id};
+pub use scoring::{score_source, Sample};
+pub use aggregation::{build_aggregator, miner_forecasts};
+pub use outcomes::{fetch_outcome, FetchOutcomeError};
+pub use ingestion::{ingest_events, ingest_markets};
+pub use pagination::{decode_cursor, encode_cursor, page_size, PageMeta};
+pub use predictions::{create_outcome_prediction, prediction_targets};
+pub use feed::run_price_feed;
+pub use scheduler::start_scheduler;
+pub use signer::{sign_transaction, wallet_signer};
+pub use transfers::{destination_account_rent, network_fee, prepare_usdc_transfer, submit_transaction, token_credit, TransferError};
\ No newline at end of file
@@ -0,0 +1,102 @@
+use std::fmt;
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::clients::prediction_api::CommunityPrediction;
+use crate::clients::ClientError;
+use crate::models::PredictionResult;
+use crate::utilities::aggregation::{miner_forecasts, Aggregator, MinerForecast};
+use crate::utilities::app_state::AppState;
+use crate::utilities::feed::FeedEvent;
+
+/// Stores an aggregated outcome together with the individual miner forecasts it was computed from.
+pub async fn store_outcome(pool: &PgPool, prediction_id: &str, weighted: f64, community: f64, aggregator: &str, raw: &Value, forecasts: &[MinerForecast]) -> Result<NaiveDateTime, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let created_at = sqlx::query_scalar!(
+        "INSERT INTO outcomes (prediction_id, weighted, community, aggregator, raw) VALUES ($1, $2, $3, $4, $5) RETURNING created_at",
+        prediction_id,
+        weighted,
+        community,
+        aggregator,
+        raw)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let (miners, values): (Vec<String>, Vec<f64>) = forecasts.iter()
+        .filter_map(|forecast| forecast.miner.clone().map(|miner| (miner, forecast.value)))
+        .unzip();
+
+    sqlx::query!(
+        "INSERT INTO miner_predictions (prediction_id, miner_id, predicted_outcome, created_at)
+        SELECT $1, miner_id, predicted_outcome, $4
+        FROM UNNEST($2::text[], $3::float8[]) AS t(miner_id, predicted_outcome)
+        ON CONFLICT DO NOTHING",
+        prediction_id,
+        &miners,
+        &values,
+        created_at)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(created_at)
+}
+
+#[derive(Debug)]
+pub enum FetchOutcomeError {
+    Api(ClientError),
+    NoPredictions,
+    NoCommunityPrediction,
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for FetchOutcomeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchOutcomeError::Api(e) => write!(f, "prediction API error: {}", e),
+            FetchOutcomeError::NoPredictions => write!(f, "no predictions available yet"),
+            FetchOutcomeError::NoCommunityPrediction => write!(f, "no community prediction available"),
+            FetchOutcomeError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+/// Fetches the current miner and community predictions from the upstream API, aggregates them, stores the outcome
+/// and publishes it to the feed. This is the only path that writes to `outcomes`.
+pub async fn fetch_outcome(state: &AppState, aggregator: &dyn Aggregator, prediction_id: &str, condition_id: &str) -> Result<PredictionResult, FetchOutcomeError> {
+    let (predictions, raw) = state.api.predictions(prediction_id).await.map_err(FetchOutcomeError::Api)?;
+
+    let forecasts = miner_forecasts(&predictions.predictions);
+    let weighted = match aggregator.aggregate(&forecasts) {
+        Some(value) => (value * 10000.0).round() / 10000.0,
+        None => return Err(FetchOutcomeError::NoPredictions)
+    };
+
+    let community = match state.api.community_prediction(prediction_id).await.map_err(FetchOutcomeError::Api)? {
+        CommunityPrediction { community_prediction: Some(value) } => (value * 10000.0).round() / 10000.0,
+        CommunityPrediction { community_prediction: None } => return Err(FetchOutcomeError::NoCommunityPrediction)
+    };
+
+    let created_at = store_outcome(&state.pool, prediction_id, weighted, community, aggregator.name(), &raw, &forecasts).await
+        .map_err(FetchOutcomeError::Database)?;
+
+    state.feed.publish(FeedEvent::Outcome {
+        prediction_id: prediction_id.to_string(),
+        condition_id: condition_id.to_string(),
+        weighted,
+        community,
+        aggregator: aggregator.name().to_string(),
+        created_at,
+    });
+
+    Ok(PredictionResult {
+        weighted,
+        community,
+        aggregator: aggregator.name().to_string(),
+        as_of: created_at,
+        stale: false,
+    })
+}
@@ -0,0 +1,77 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64, Engine};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const DEFAULT_PAGE_SIZE: i64 = 100;
+pub const MAX_PAGE_SIZE: i64 = 500;
+
+#[derive(Serialize)]
+pub struct PageMeta {
+    pub limit: i64,
+    pub next_cursor: Option<String>,
+}
+
+/// Cursors are opaque to clients: the keyset position of the last row, as URL-safe base64 JSON.
+pub fn encode_cursor<T: Serialize>(position: &T) -> String {
+    BASE64.encode(serde_json::to_vec(position).unwrap())
+}
+
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> Option<T> {
+    let bytes = BASE64.decode(cursor).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Validates a requested page size, falling back to the default when none is given.
+pub fn page_size(limit: Option<i64>) -> Option<i64> {
+    match limit {
+        None => Some(DEFAULT_PAGE_SIZE),
+        Some(limit) if limit > 0 && limit <= MAX_PAGE_SIZE => Some(limit),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::models::MarketCursor;
+
+    #[test]
+    fn cursor_round_trips() {
+        let timestamp = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().and_hms_opt(12, 30, 0).unwrap();
+        let cursor = encode_cursor(&MarketCursor {
+            sort: "end_date".to_string(),
+            timestamp: Some(timestamp),
+            divergence: None,
+            condition_id: "0xabc".to_string(),
+        });
+
+        let decoded: MarketCursor = decode_cursor(&cursor).unwrap();
+
+        assert_eq!(decoded.sort, "end_date");
+        assert_eq!(decoded.timestamp, Some(timestamp));
+        assert_eq!(decoded.divergence, None);
+        assert_eq!(decoded.condition_id, "0xabc");
+    }
+
+    #[test]
+    fn cursor_is_url_safe() {
+        let cursor = encode_cursor(&vec!["???>>>~~~"; 8]);
+
+        assert!(cursor.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    }
+
+    #[test]
+    fn invalid_cursors_decode_to_none() {
+        assert!(decode_cursor::<MarketCursor>("not a cursor!").is_none());
+        assert!(decode_cursor::<MarketCursor>(&encode_cursor(&"plain string")).is_none());
+        assert!(decode_cursor::<MarketCursor>("").is_none());
+    }
+
+    #[test]
+    fn page_size_is_bounded() {
+        assert_eq!(page_size(None), Some(DEFAULT_PAGE_SIZE));
+        assert_eq!(page_size(Some(MAX_PAGE_SIZE)), Some(MAX_PAGE_SIZE));
+        assert_eq!(page_size(Some(0)), None);
+        assert_eq!(page_size(Some(MAX_PAGE_SIZE + 1)), None);
+    }
+}
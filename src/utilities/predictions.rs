@@ -0,0 +1,83 @@
+use std::fmt;
+use chrono::SecondsFormat;
+use sqlx::PgPool;
+
+use crate::clients::prediction_api::CreateEventRequest;
+use crate::clients::ClientError;
+use crate::models::{Market, MarketOutcome, Prediction};
+use crate::utilities::app_state::AppState;
+
+#[derive(Debug)]
+pub enum CreatePredictionError {
+    Api(ClientError),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for CreatePredictionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CreatePredictionError::Api(e) => write!(f, "prediction API error: {}", e),
+            CreatePredictionError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+/// Outcomes of a market that still need a prediction. Binary markets are predicted on their YES token only and
+/// multi-outcome markets get one prediction per outcome, unless a specific `token_id` is requested.
+pub async fn prediction_targets(pool: &PgPool, market: &Market, token_id: Option<&str>) -> Result<Vec<MarketOutcome>, sqlx::Error> {
+    sqlx::query_as!(
+        MarketOutcome,
+        "SELECT mo.token_id, mo.condition_id, mo.outcome, mo.position, mo.winner
+        FROM market_outcomes mo
+        WHERE mo.condition_id = $1
+        AND ($2::text IS NULL OR mo.token_id = $2)
+        AND NOT EXISTS (
+            SELECT 1 FROM predictions p
+            WHERE p.condition_id = mo.condition_id AND p.token_id = mo.token_id
+        )
+        ORDER BY mo.position",
+        market.condition_id,
+        token_id.or(market.yes_token_id.as_deref()))
+        .fetch_all(pool)
+        .await
+}
+
+/// Creates an upstream event asking whether `outcome` wins and records it as a prediction.
+pub async fn create_outcome_prediction(state: &AppState, market: &Market, outcome: &MarketOutcome) -> Result<Prediction, CreatePredictionError> {
+    let binary = market.yes_token_id.as_deref() == Some(outcome.token_id.as_str());
+
+    let request = CreateEventRequest {
+        title: if binary {
+            market.question.clone()
+        } else {
+            format!("{} ({})", market.question, outcome.outcome)
+        },
+        description: if binary {
+            market.description.clone()
+        } else {
+            format!("{}\n\nThis event resolves YES if the outcome \"{}\" wins, and NO otherwise.", market.description, outcome.outcome)
+        },
+        cutoff: market.end_date.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true),
+    };
+
+    let prediction_id = state.api.create_event(&request).await
+        .map_err(CreatePredictionError::Api)?
+        .event_id;
+
+    sqlx::query!(
+        "INSERT INTO predictions (prediction_id, condition_id, token_id, end_date, created_at) VALUES ($1, $2, $3, $4, NOW())",
+        prediction_id,
+        market.condition_id,
+        outcome.token_id,
+        market.end_date)
+        .execute(&*state.pool)
+        .await
+        .map_err(CreatePredictionError::Database)?;
+
+    Ok(Prediction {
+        prediction_id,
+        condition_id: market.condition_id.clone(),
+        token_id: Some(outcome.token_id.clone()),
+        end_date: market.end_date,
+    })
+}
@@ -9,6 +9,8 @@ use serde_json::{to_string, to_value, Value};
 pub struct JsonResponse<T> where T: Serialize, {
     success: bool,
     response: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Value>,
     #[serde(skip)]
     status_code: StatusCode,
 }
@@ -18,6 +20,16 @@ impl JsonResponse<Value> {
         JsonResponse {
             success: true,
             response: to_value(response).unwrap(),
+            meta: None,
+            status_code,
+        }
+    }
+
+    pub fn success_with_meta<T: Serialize, M: Serialize>(response: T, meta: M, status_code: StatusCode) -> Self {
+        JsonResponse {
+            success: true,
+            response: to_value(response).unwrap(),
+            meta: Some(to_value(meta).unwrap()),
             status_code,
         }
     }
@@ -26,6 +38,7 @@ impl JsonResponse<Value> {
         JsonResponse {
             success: false,
             response: to_value(msg.into()).unwrap(),
+            meta: None,
             status_code,
         }
     }
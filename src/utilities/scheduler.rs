@@ -0,0 +1,388 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{NaiveDateTime, Utc};
+use serde_json::Value;
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+
+use crate::utilities::app_state::AppState;
+use crate::utilities::tasks::jobs;
+
+/// First key of every job's advisory lock; the second is `hashtext(job name)`.
+const LOCK_NAMESPACE: i32 = 7301;
+
+/// How long a replica waits before checking again after losing the lock or failing to reach the database.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Counters reported by a successful run, or an error message.
+pub type JobResult = Result<Value, String>;
+
+type JobFuture = Pin<Box<dyn Future<Output = JobResult> + Send>>;
+
+#[derive(Clone)]
+pub enum Schedule {
+    Interval(Duration),
+    Cron(Box<cron::Schedule>),
+    /// Runs right after each scheduled run of the named job, on the same replica.
+    After(String),
+    Disabled,
+}
+
+impl Schedule {
+    /// Parses a number of seconds, a cron expression with a leading seconds field (e.g. `0 15 * * * *`),
+    /// `after:<job>`, or `off`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+
+        if value.eq_ignore_ascii_case("off") {
+            return Ok(Schedule::Disabled);
+        }
+
+        if let Some(job) = value.strip_prefix("after:") {
+            let job = job.trim();
+            if job.is_empty() {
+                return Err("after: needs a job name".to_string());
+            }
+            return Ok(Schedule::After(job.to_string()));
+        }
+
+        if let Ok(seconds) = value.parse::<u64>() {
+            if seconds == 0 {
+                return Err("interval must be at least one second".to_string());
+            }
+            return Ok(Schedule::Interval(Duration::from_secs(seconds)));
+        }
+
+        cron::Schedule::from_str(value)
+            .map(|schedule| Schedule::Cron(Box::new(schedule)))
+            .map_err(|e| e.to_string())
+    }
+
+    /// When the job is next due, given when it last started. Jobs that have never run are due immediately; a run
+    /// missed while no replica was up happens as soon as one starts. Jobs that run after another are never due on
+    /// their own.
+    pub fn next_run(&self, last_started: Option<NaiveDateTime>) -> Option<NaiveDateTime> {
+        let last_started = match last_started {
+            Some(last_started) => last_started,
+            None => return match self {
+                Schedule::After(_) | Schedule::Disabled => None,
+                _ => Some(Utc::now().naive_utc()),
+            },
+        };
+
+        match self {
+            Schedule::Interval(interval) => chrono::Duration::from_std(*interval).ok()
+                .and_then(|interval| last_started.checked_add_signed(interval)),
+            Schedule::Cron(schedule) => schedule.after(&last_started.and_utc()).next()
+                .map(|next| next.naive_utc()),
+            Schedule::After(_) | Schedule::Disabled => None,
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Interval(interval) => write!(f, "every {}s", interval.as_secs()),
+            Schedule::Cron(schedule) => write!(f, "{}", schedule),
+            Schedule::After(job) => write!(f, "after {}", job),
+            Schedule::Disabled => write!(f, "off"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Job {
+    pub name: &'static str,
+    pub schedule: Schedule,
+    run: fn(Arc<AppState>) -> JobFuture,
+}
+
+impl Job {
+    pub fn new(name: &'static str, schedule: Schedule, run: fn(Arc<AppState>) -> JobFuture) -> Self {
+        Job { name, schedule, run }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Trigger {
+    Schedule,
+    Dependency,
+    Manual,
+}
+
+impl Trigger {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Trigger::Schedule => "schedule",
+            Trigger::Dependency => "dependency",
+            Trigger::Manual => "manual",
+        }
+    }
+}
+
+/// A job run that holds the job's advisory lock on its own connection until it finishes.
+pub struct ActiveRun {
+    pub id: i64,
+    job: Job,
+    conn: PoolConnection<Postgres>,
+}
+
+/// Spawns one loop per enabled job that has its own schedule. Every replica runs the loops; the advisory lock and
+/// the run history in `job_runs` make sure each due run happens on exactly one of them.
+pub fn start_scheduler(state: Arc<AppState>) {
+    for job in jobs(&state.config) {
+        match &job.schedule {
+            Schedule::Disabled => {
+                println!("Task: Job {} is disabled", job.name);
+                continue;
+            }
+            Schedule::After(after) => {
+                println!("Task: Job {} runs after {}", job.name, after);
+                continue;
+            }
+            _ => {}
+        }
+
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            run_schedule(state, job).await;
+        });
+    }
+}
+
+async fn run_schedule(state: Arc<AppState>, job: Job) {
+    loop {
+        let last_started = match last_started(&state.pool, job.name).await {
+            Ok(last_started) => last_started,
+            Err(e) => {
+                eprintln!("Task: Error fetching last run of {}: {}", job.name, e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        let next_run = match job.schedule.next_run(last_started) {
+            Some(next_run) => next_run,
+            None => return,
+        };
+
+        // Sleep until due, then re-read the history in case another replica ran the job in the meantime.
+        if let Ok(wait) = (next_run - Utc::now().naive_utc()).to_std() {
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+        }
+
+        match start_run(&state, &job, Trigger::Schedule).await {
+            Ok(Some(run)) => {
+                run.execute(Arc::clone(&state)).await;
+                run_dependents(&state, job.name).await;
+            }
+            Ok(None) => tokio::time::sleep(RETRY_DELAY).await,
+            Err(e) => {
+                eprintln!("Task: Error starting {}: {}", job.name, e);
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Runs every job scheduled `after:` the given one, then the jobs after those, in configuration order. Dependents
+/// run even if the job before them failed, and are skipped only while another run of them is in progress.
+async fn run_dependents(state: &Arc<AppState>, name: &'static str) {
+    let jobs = jobs(&state.config);
+    let mut finished = VecDeque::from([name]);
+
+    while let Some(name) = finished.pop_front() {
+        for job in jobs.iter().filter(|job| matches!(&job.schedule, Schedule::After(after) if after == name)) {
+            match start_run(state, job, Trigger::Dependency).await {
+                Ok(Some(run)) => run.execute(Arc::clone(state)).await,
+                Ok(None) => println!("Task: Skipping {}, another run is in progress", job.name),
+                Err(e) => eprintln!("Task: Error starting {}: {}", job.name, e),
+            }
+            finished.push_back(job.name);
+        }
+    }
+}
+
+async fn last_started(pool: &PgPool, job: &str) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "SELECT MAX(started_at) FROM job_runs WHERE job = $1",
+        job)
+        .fetch_one(pool)
+        .await
+}
+
+/// Takes the job's advisory lock and records a new run. Returns `None` when another run holds the lock or, for
+/// scheduled runs, when another replica already ran the job since it became due.
+pub async fn start_run(state: &AppState, job: &Job, trigger: Trigger) -> Result<Option<ActiveRun>, sqlx::Error> {
+    let mut conn = state.pool.acquire().await?;
+
+    let locked = sqlx::query_scalar!(
+        "SELECT pg_try_advisory_lock($1, hashtext($2)) AS \"locked!\"",
+        LOCK_NAMESPACE,
+        job.name)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    if !locked {
+        return Ok(None);
+    }
+
+    let result = record_start(&mut conn, job, trigger).await;
+    match result {
+        Ok(Some(id)) => Ok(Some(ActiveRun { id, job: job.clone(), conn })),
+        Ok(None) => {
+            unlock(conn, job.name).await;
+            Ok(None)
+        }
+        Err(e) => {
+            unlock(conn, job.name).await;
+            Err(e)
+        }
+    }
+}
+
+async fn record_start(conn: &mut PoolConnection<Postgres>, job: &Job, trigger: Trigger) -> Result<Option<i64>, sqlx::Error> {
+    if let Trigger::Schedule = trigger {
+        let last_started = sqlx::query_scalar!(
+            "SELECT MAX(started_at) FROM job_runs WHERE job = $1",
+            job.name)
+            .fetch_one(&mut **conn)
+            .await?;
+
+        if job.schedule.next_run(last_started).is_none_or(|next_run| next_run > Utc::now().naive_utc()) {
+            return Ok(None);
+        }
+    }
+
+    // Holding the lock means no other run is in progress, so anything still marked running died with its replica.
+    sqlx::query!(
+        "UPDATE job_runs SET status = 'abandoned', finished_at = NOW() WHERE job = $1 AND status = 'running'",
+        job.name)
+        .execute(&mut **conn)
+        .await?;
+
+    let id = sqlx::query_scalar!(
+        "INSERT INTO job_runs (job, triggered_by) VALUES ($1, $2) RETURNING id",
+        job.name,
+        trigger.as_str())
+        .fetch_one(&mut **conn)
+        .await?;
+
+    Ok(Some(id))
+}
+
+impl ActiveRun {
+    /// Runs the job, records its outcome and releases the lock.
+    pub async fn execute(mut self, state: Arc<AppState>) {
+        println!("Task: Starting {}", self.job.name);
+
+        // Run on a separate task so a panicking job is recorded as failed instead of leaking the lock.
+        let result = match tokio::spawn((self.job.run)(state)).await {
+            Ok(result) => result,
+            Err(e) => Err(format!("job panicked: {}", e)),
+        };
+
+        let (status, counters, error) = match result {
+            Ok(counters) => ("succeeded", Some(counters), None),
+            Err(e) => {
+                eprintln!("Task: Job {} failed: {}", self.job.name, e);
+                ("failed", None, Some(e))
+            }
+        };
+
+        let result = sqlx::query!(
+            "UPDATE job_runs SET status = $2, counters = $3, error = $4, finished_at = NOW() WHERE id = $1",
+            self.id,
+            status,
+            counters,
+            error)
+            .execute(&mut *self.conn)
+            .await;
+
+        if let Err(e) = result {
+            eprintln!("Task: Error recording run {} of {}: {}", self.id, self.job.name, e);
+        }
+
+        println!("Task: Finished {} ({})", self.job.name, status);
+        unlock(self.conn, self.job.name).await;
+    }
+}
+
+async fn unlock(mut conn: PoolConnection<Postgres>, job: &str) {
+    let result = sqlx::query_scalar!(
+        "SELECT pg_advisory_unlock($1, hashtext($2))",
+        LOCK_NAMESPACE,
+        job)
+        .fetch_one(&mut *conn)
+        .await;
+
+    // Closing the session is the only other way to release a session-level lock.
+    if let Err(e) = result {
+        eprintln!("Task: Error releasing lock for {}: {}", job, e);
+        drop(conn.detach());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().and_hms_opt(hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn parses_every_schedule_kind() {
+        assert!(matches!(Schedule::parse("3900"), Ok(Schedule::Interval(interval)) if interval == Duration::from_secs(3900)));
+        assert!(matches!(Schedule::parse(" OFF "), Ok(Schedule::Disabled)));
+        assert!(matches!(Schedule::parse("after:create_markets"), Ok(Schedule::After(job)) if job == "create_markets"));
+        assert!(matches!(Schedule::parse("0 15 * * * *"), Ok(Schedule::Cron(_))));
+
+        assert!(Schedule::parse("0").is_err());
+        assert!(Schedule::parse("after:").is_err());
+        assert!(Schedule::parse("every hour").is_err());
+    }
+
+    #[test]
+    fn interval_runs_after_the_last_start() {
+        let schedule = Schedule::parse("300").unwrap();
+
+        assert_eq!(schedule.next_run(Some(at(12, 0, 0))), Some(at(12, 5, 0)));
+    }
+
+    #[test]
+    fn cron_runs_at_the_next_matching_time() {
+        let schedule = Schedule::parse("0 15 * * * *").unwrap();
+
+        assert_eq!(schedule.next_run(Some(at(12, 0, 0))), Some(at(12, 15, 0)));
+        assert_eq!(schedule.next_run(Some(at(12, 15, 0))), Some(at(13, 15, 0)));
+    }
+
+    #[test]
+    fn jobs_that_never_ran_are_due_now() {
+        let before = Utc::now().naive_utc();
+
+        let next_run = Schedule::parse("3900").unwrap().next_run(None).unwrap();
+        assert!(next_run >= before && next_run <= Utc::now().naive_utc());
+
+        assert!(Schedule::parse("0 15 * * * *").unwrap().next_run(None).is_some());
+    }
+
+    #[test]
+    fn disabled_and_dependent_jobs_are_never_due() {
+        for schedule in [Schedule::Disabled, Schedule::After("create_markets".to_string())] {
+            assert_eq!(schedule.next_run(None), None);
+            assert_eq!(schedule.next_run(Some(at(12, 0, 0))), None);
+        }
+    }
+}
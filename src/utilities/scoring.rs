@@ -0,0 +1,130 @@
+use serde::Serialize;
+
+const EPSILON: f64 = 1e-6;
+
+#[derive(Serialize)]
+pub struct ReliabilityBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    pub mean_forecast: Option<f64>,
+    pub observed_frequency: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct SourceScore {
+    pub source: String,
+    pub count: usize,
+    pub brier: Option<f64>,
+    pub log_loss: Option<f64>,
+    pub reliability: Vec<ReliabilityBucket>,
+}
+
+/// A forecast probability paired with the realised outcome (1.0 for YES, 0.0 for NO).
+pub type Sample = (f64, f64);
+
+pub fn brier_score(samples: &[Sample]) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let sum: f64 = samples.iter()
+        .map(|(forecast, outcome)| (forecast - outcome).powi(2))
+        .sum();
+
+    Some(round(sum / samples.len() as f64))
+}
+
+pub fn log_loss(samples: &[Sample]) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let sum: f64 = samples.iter()
+        .map(|(forecast, outcome)| {
+            let forecast = forecast.clamp(EPSILON, 1.0 - EPSILON);
+            -(outcome * forecast.ln() + (1.0 - outcome) * (1.0 - forecast).ln())
+        })
+        .sum();
+
+    Some(round(sum / samples.len() as f64))
+}
+
+pub fn reliability(samples: &[Sample], buckets: usize) -> Vec<ReliabilityBucket> {
+    let buckets = buckets.max(1);
+    let width = 1.0 / buckets as f64;
+    let mut totals = vec![(0usize, 0.0, 0.0); buckets];
+
+    for (forecast, outcome) in samples {
+        let index = ((forecast / width) as usize).min(buckets - 1);
+        totals[index].0 += 1;
+        totals[index].1 += forecast;
+        totals[index].2 += outcome;
+    }
+
+    totals.into_iter()
+        .enumerate()
+        .map(|(index, (count, forecast_sum, outcome_sum))| ReliabilityBucket {
+            lower: round(index as f64 * width),
+            upper: round((index + 1) as f64 * width),
+            count,
+            mean_forecast: (count > 0).then(|| round(forecast_sum / count as f64)),
+            observed_frequency: (count > 0).then(|| round(outcome_sum / count as f64)),
+        })
+        .collect()
+}
+
+pub fn score_source(source: &str, samples: &[Sample], buckets: usize) -> SourceScore {
+    SourceScore {
+        source: source.to_string(),
+        count: samples.len(),
+        brier: brier_score(samples),
+        log_loss: log_loss(samples),
+        reliability: reliability(samples, buckets),
+    }
+}
+
+/// Rounds to the four decimal places probabilities and prices are reported with.
+pub fn round(value: f64) -> f64 {
+    (value * 10000.0).round() / 10000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brier_score_averages_squared_errors() {
+        assert_eq!(brier_score(&[]), None);
+        assert_eq!(brier_score(&[(1.0, 1.0), (0.0, 0.0)]), Some(0.0));
+        assert_eq!(brier_score(&[(0.8, 1.0), (0.3, 0.0)]), Some(0.065));
+        assert_eq!(brier_score(&[(0.5, 1.0), (0.5, 0.0)]), Some(0.25));
+    }
+
+    #[test]
+    fn log_loss_clamps_certain_misses() {
+        assert_eq!(log_loss(&[]), None);
+        assert_eq!(log_loss(&[(0.8, 1.0), (0.2, 0.0)]), Some(0.2231));
+        // A confident miss is heavily penalised but still finite.
+        assert_eq!(log_loss(&[(0.0, 1.0)]), Some(13.8155));
+    }
+
+    #[test]
+    fn reliability_buckets_forecasts_by_probability() {
+        let buckets = reliability(&[(0.05, 0.0), (0.15, 0.0), (0.12, 1.0), (1.0, 1.0)], 10);
+
+        assert_eq!(buckets.len(), 10);
+        assert_eq!((buckets[0].lower, buckets[0].upper, buckets[0].count), (0.0, 0.1, 1));
+        assert_eq!((buckets[1].mean_forecast, buckets[1].observed_frequency), (Some(0.135), Some(0.5)));
+        // A forecast of exactly 1.0 falls into the top bucket.
+        assert_eq!((buckets[9].count, buckets[9].observed_frequency), (1, Some(1.0)));
+        assert_eq!((buckets[5].count, buckets[5].mean_forecast), (0, None));
+    }
+
+    #[test]
+    fn rounds_to_four_decimal_places() {
+        assert_eq!(round(0.123449), 0.1234);
+        assert_eq!(round(0.12345), 0.1235);
+        assert_eq!(round(1.0 / 3.0), 0.3333);
+    }
+}
@@ -0,0 +1,411 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer as _;
+use solana_sdk::transaction::VersionedTransaction;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::models::Wallet;
+use crate::utilities::app_state::{AppState, Config, MasterKeyConfig};
+use crate::utilities::encryption::{wallet_secret, EncryptionError, KeyRing, RotationReport, SealedSecret};
+
+pub const SIGNERS: [&str; 3] = ["database", "keystore", "socket"];
+
+#[derive(Debug)]
+pub enum SignerError {
+    Encryption(EncryptionError),
+    InvalidKey(String),
+    KeyNotFound(String),
+    NotASigner(String),
+    Io(std::io::Error),
+    Remote(String),
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerError::Encryption(e) => write!(f, "{}", e),
+            SignerError::InvalidKey(e) => write!(f, "invalid key: {}", e),
+            SignerError::KeyNotFound(address) => write!(f, "no key for {}", address),
+            SignerError::NotASigner(address) => write!(f, "{} is not a required signer of the transaction", address),
+            SignerError::Io(e) => write!(f, "{}", e),
+            SignerError::Remote(e) => write!(f, "signer process: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SignerError {}
+
+impl From<EncryptionError> for SignerError {
+    fn from(e: EncryptionError) -> Self {
+        SignerError::Encryption(e)
+    }
+}
+
+impl From<std::io::Error> for SignerError {
+    fn from(e: std::io::Error) -> Self {
+        SignerError::Io(e)
+    }
+}
+
+/// Custody of wallet private keys. Callers hand over messages to sign and never see the key itself.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Takes custody of a new wallet's base58 private key. Returns the sealed secret to store in the wallet row, or
+    /// `None` when the signer keeps the key itself.
+    async fn import(&self, account_id: &str, address: &str, secret: &str) -> Result<Option<SealedSecret>, SignerError>;
+
+    /// Signs `message` with the wallet's key.
+    async fn sign(&self, wallet: &Wallet, message: &[u8]) -> Result<Signature, SignerError>;
+}
+
+/// Keys sealed into the `wallets` row itself.
+pub struct DatabaseSigner {
+    keys: Arc<KeyRing>,
+}
+
+impl DatabaseSigner {
+    pub fn new(keys: Arc<KeyRing>) -> Self {
+        DatabaseSigner { keys }
+    }
+}
+
+#[async_trait]
+impl Signer for DatabaseSigner {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+
+    async fn import(&self, account_id: &str, _address: &str, secret: &str) -> Result<Option<SealedSecret>, SignerError> {
+        Ok(Some(self.keys.seal(secret, account_id)?))
+    }
+
+    async fn sign(&self, wallet: &Wallet, message: &[u8]) -> Result<Signature, SignerError> {
+        let keypair = keypair(&wallet_secret(&self.keys, wallet)?, &wallet.address)?;
+        Ok(keypair.sign_message(message))
+    }
+}
+
+/// Keys sealed into one file per wallet address under a local directory, readable only by the owning user.
+pub struct KeystoreSigner {
+    dir: PathBuf,
+    keys: Arc<KeyRing>,
+}
+
+impl KeystoreSigner {
+    pub fn new(dir: impl Into<PathBuf>, keys: Arc<KeyRing>) -> Self {
+        KeystoreSigner { dir: dir.into(), keys }
+    }
+
+    fn path(&self, address: &str) -> Result<PathBuf, SignerError> {
+        // Addresses become file names, so only accept valid public keys.
+        Pubkey::from_str(address).map_err(|e| SignerError::InvalidKey(format!("{}: {}", address, e)))?;
+        Ok(self.dir.join(format!("{}.json", address)))
+    }
+
+    async fn store(&self, address: &str, secret: &str) -> Result<(), SignerError> {
+        keypair(secret, address)?;
+
+        let sealed = self.keys.seal(secret, address)?;
+        let contents = serde_json::to_vec(&sealed).map_err(|e| SignerError::InvalidKey(e.to_string()))?;
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(self.path(address)?)
+            .await?;
+        file.write_all(&contents).await?;
+        file.sync_all().await?;
+
+        Ok(())
+    }
+
+    async fn sign_as(&self, address: &str, message: &[u8]) -> Result<Signature, SignerError> {
+        let contents = match tokio::fs::read(self.path(address)?).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(SignerError::KeyNotFound(address.to_string())),
+            Err(e) => return Err(e.into()),
+        };
+        let sealed: SealedSecret = serde_json::from_slice(&contents).map_err(|_| EncryptionError::Malformed)?;
+
+        let keypair = keypair(&self.keys.open(&sealed, address)?, address)?;
+        Ok(keypair.sign_message(message))
+    }
+
+    /// Moves every key file onto the active master key, like `rotate_wallet_keys` does for wallet rows. Each file
+    /// is replaced by renaming a fully written copy over it.
+    pub async fn rotate_keys(&self, reencrypt: bool) -> Result<RotationReport, std::io::Error> {
+        let mut report = RotationReport::default();
+
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(e),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let address = match (path.extension().and_then(|ext| ext.to_str()), path.file_stem().and_then(|stem| stem.to_str())) {
+                (Some("json"), Some(address)) => address.to_string(),
+                _ => continue,
+            };
+
+            match self.rotate_key(&address, reencrypt).await {
+                Ok(true) => report.rewrapped += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("Error rotating key file for {}: {}", address, e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Returns whether the file needed rotating.
+    async fn rotate_key(&self, address: &str, reencrypt: bool) -> Result<bool, SignerError> {
+        let path = self.path(address)?;
+        let contents = tokio::fs::read(&path).await?;
+        let sealed: SealedSecret = serde_json::from_slice(&contents).map_err(|_| EncryptionError::Malformed)?;
+
+        if sealed.key_version == self.keys.active_version() && !reencrypt {
+            return Ok(false);
+        }
+
+        let rotated = match reencrypt {
+            true => self.keys.seal(&self.keys.open(&sealed, address)?, address)?,
+            false => self.keys.rewrap(&sealed, address)?,
+        };
+        let contents = serde_json::to_vec(&rotated).map_err(|e| SignerError::InvalidKey(e.to_string()))?;
+
+        let staged = self.dir.join(format!(".{}.json.tmp", address));
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&staged)
+            .await?;
+        file.write_all(&contents).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&staged, &path).await?;
+
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Signer for KeystoreSigner {
+    fn name(&self) -> &'static str {
+        "keystore"
+    }
+
+    async fn import(&self, _account_id: &str, address: &str, secret: &str) -> Result<Option<SealedSecret>, SignerError> {
+        self.store(address, secret).await?;
+        Ok(None)
+    }
+
+    async fn sign(&self, wallet: &Wallet, message: &[u8]) -> Result<Signature, SignerError> {
+        self.sign_as(&wallet.address, message).await
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum SignerRequest {
+    Import { address: String, secret: String },
+    Sign { address: String, message: String },
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SignerResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A separate `predictions-api signer` process holding the keystore, reached over a Unix socket with one JSON
+/// request and response per line. The API process never reads the key files.
+pub struct SocketSigner {
+    path: PathBuf,
+}
+
+impl SocketSigner {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        SocketSigner { path: path.into() }
+    }
+
+    async fn request(&self, request: &SignerRequest) -> Result<SignerResponse, SignerError> {
+        let mut line = serde_json::to_vec(request).map_err(|e| SignerError::Remote(e.to_string()))?;
+        line.push(b'\n');
+
+        let mut stream = UnixStream::connect(&self.path).await?;
+        stream.write_all(&line).await?;
+
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response).await?;
+
+        let response: SignerResponse = serde_json::from_str(&response)
+            .map_err(|e| SignerError::Remote(format!("invalid response: {}", e)))?;
+
+        match response.error {
+            Some(error) => Err(SignerError::Remote(error)),
+            None => Ok(response),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for SocketSigner {
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    async fn import(&self, _account_id: &str, address: &str, secret: &str) -> Result<Option<SealedSecret>, SignerError> {
+        self.request(&SignerRequest::Import { address: address.to_string(), secret: secret.to_string() }).await?;
+        Ok(None)
+    }
+
+    async fn sign(&self, wallet: &Wallet, message: &[u8]) -> Result<Signature, SignerError> {
+        let response = self.request(&SignerRequest::Sign {
+            address: wallet.address.clone(),
+            message: BASE64.encode(message),
+        }).await?;
+
+        response.signature
+            .and_then(|signature| Signature::from_str(&signature).ok())
+            .ok_or_else(|| SignerError::Remote("missing signature".to_string()))
+    }
+}
+
+pub fn build_signer(config: &Config, keys: Arc<KeyRing>) -> Option<Arc<dyn Signer>> {
+    match config.signer.as_str() {
+        "database" => Some(Arc::new(DatabaseSigner::new(keys))),
+        "keystore" => Some(Arc::new(KeystoreSigner::new(&config.signer_keystore_dir, keys))),
+        "socket" => Some(Arc::new(SocketSigner::new(&config.signer_socket))),
+        _ => None,
+    }
+}
+
+/// The signer holding the wallet's key. Wallets whose secret is sealed into their row are signed from it whatever
+/// the configured signer, so switching signers does not strand existing wallets.
+pub fn wallet_signer(state: &AppState, wallet: &Wallet) -> Arc<dyn Signer> {
+    match wallet.secret {
+        Some(_) => Arc::new(DatabaseSigner::new(Arc::clone(&state.keys))),
+        None => Arc::clone(&state.signer),
+    }
+}
+
+/// Adds the wallet's signature to a transaction, leaving any other signatures in place.
+pub async fn sign_transaction(signer: &dyn Signer, wallet: &Wallet, transaction: &mut VersionedTransaction) -> Result<(), SignerError> {
+    let pubkey = Pubkey::from_str(&wallet.address).map_err(|e| SignerError::InvalidKey(e.to_string()))?;
+    let required = transaction.message.header().num_required_signatures as usize;
+
+    let index = transaction.message.static_account_keys().iter()
+        .take(required)
+        .position(|key| *key == pubkey)
+        .ok_or_else(|| SignerError::NotASigner(wallet.address.clone()))?;
+
+    let signature = signer.sign(wallet, &transaction.message.serialize()).await?;
+
+    transaction.signatures.resize(required, Signature::default());
+    transaction.signatures[index] = signature;
+
+    Ok(())
+}
+
+/// Decodes a base58 private key, checking that it belongs to `address`.
+pub(crate) fn keypair(secret: &str, address: &str) -> Result<Keypair, SignerError> {
+    let bytes = bs58::decode(secret).into_vec().map_err(|e| SignerError::InvalidKey(e.to_string()))?;
+    let keypair = Keypair::try_from(bytes.as_slice()).map_err(|e| SignerError::InvalidKey(e.to_string()))?;
+
+    if keypair.pubkey().to_string() != address {
+        return Err(SignerError::InvalidKey(format!("key does not match {}", address)));
+    }
+
+    Ok(keypair)
+}
+
+async fn handle_connection(signer: &KeystoreSigner, stream: UnixStream) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let result = match serde_json::from_str::<SignerRequest>(&line) {
+            Ok(SignerRequest::Import { address, secret }) => signer.store(&address, &secret).await
+                .map(|_| SignerResponse::default()),
+            Ok(SignerRequest::Sign { address, message }) => match BASE64.decode(&message) {
+                Ok(message) => signer.sign_as(&address, &message).await
+                    .map(|signature| SignerResponse { signature: Some(signature.to_string()), error: None }),
+                Err(e) => Err(SignerError::Remote(format!("invalid message: {}", e))),
+            },
+            Err(e) => Err(SignerError::Remote(format!("invalid request: {}", e))),
+        };
+
+        let response = result.unwrap_or_else(|e| {
+            eprintln!("Signer: {}", e);
+            SignerResponse { signature: None, error: Some(e.to_string()) }
+        });
+
+        let mut line = serde_json::to_vec(&response).unwrap_or_default();
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+    }
+
+    Ok(())
+}
+
+/// Binds the socket inside a fresh owner-only directory and makes it owner-only before renaming it into place, so
+/// no other user can connect in between.
+fn bind_socket(path: &Path) -> std::io::Result<UnixListener> {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("signer.sock");
+    let staging = path.with_file_name(format!(".{}.bind", name));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::os::unix::fs::DirBuilderExt::mode(&mut std::fs::DirBuilder::new(), 0o700).create(&staging)?;
+
+    let staged = staging.join(name);
+    let result = UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+
+    std::fs::remove_dir_all(&staging)?;
+    result
+}
+
+/// Entry point for `predictions-api signer`: serves the keystore on `SIGNER_SOCKET` for API processes running with
+/// `SIGNER=socket`. Only needs the master keys and `SIGNER_KEYSTORE_DIR`.
+pub async fn signer_command() -> Result<(), Box<dyn std::error::Error>> {
+    let keys = Arc::new(KeyRing::from_config(&MasterKeyConfig::from_env())?);
+    let signer = Arc::new(KeystoreSigner::new(Config::signer_keystore_dir_var(), keys));
+
+    let socket = Config::signer_socket_var();
+    let listener = bind_socket(Path::new(&socket))?;
+    println!("Signer: Listening on {}", socket);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let signer = Arc::clone(&signer);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(&signer, stream).await {
+                eprintln!("Signer: Connection error: {}", e);
+            }
+        });
+    }
+}
@@ -1,344 +1,223 @@
-use chrono::{DateTime, SecondsFormat, Utc};
-use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
+use serde_json::json;
+use solana_sdk::signature::Signature;
 
 use crate::prelude::*;
+use crate::utilities::scheduler::{Job, JobResult, Schedule};
+use crate::utilities::transfers::signature_statuses;
+
+/// Scheduled jobs and their default schedules, overridable with `JOB_<NAME>_SCHEDULE`. The hourly jobs run as one
+/// chain in the order each depends on the previous one's data.
+pub const JOB_SCHEDULES: [(&str, &str); 7] = [
+    ("create_markets", "3900"),
+    ("create_events", "after:create_markets"),
+    ("track_prices", "after:create_events"),
+    ("track_predictions", "after:track_prices"),
+    ("resolve_markets", "after:track_predictions"),
+    ("create_predictions", "after:resolve_markets"),
+    ("reconcile_transactions", "300"),
+];
+
+pub fn jobs(config: &Config) -> Vec<Job> {
+    let schedule = |name: &str| Schedule::parse(&config.job_schedules[name]).expect("Invalid job schedule");
+
+    vec![
+        Job::new("create_markets", schedule("create_markets"), |state| Box::pin(create_markets(state))),
+        Job::new("create_events", schedule("create_events"), |state| Box::pin(create_events(state))),
+        Job::new("track_prices", schedule("track_prices"), |state| Box::pin(track_prices(state))),
+        Job::new("track_predictions", schedule("track_predictions"), |state| Box::pin(track_predictions(state))),
+        Job::new("resolve_markets", schedule("resolve_markets"), |state| Box::pin(resolve_markets(state))),
+        Job::new("create_predictions", schedule("create_predictions"), |state| Box::pin(create_predictions(state))),
+        Job::new("reconcile_transactions", schedule("reconcile_transactions"), |state| Box::pin(reconcile_transactions(state))),
+    ]
+}
 
-pub async fn start_tasks(app_state: Arc<AppState>) {
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3900));
-
-    interval.tick().await;
-    interval.tick().await;
-    create_markets(app_state.clone()).await;
-    track_predictions(app_state.clone()).await;
-    create_predictions(app_state.clone()).await;
-
-    loop {
-        interval.tick().await;
-
-        let result = sqlx::query!(
-            "SELECT kv_key FROM kv WHERE kv_key = $1 AND kv_ts < NOW()",
-            "predictions_task")
-            .fetch_optional(&*app_state.pool)
-            .await;
-
-        match result {
-            Ok(Some(_)) => {
-                println!("Task: Starting tasks");
-                create_markets(app_state.clone()).await;
-                track_predictions(app_state.clone()).await;
-                create_predictions(app_state.clone()).await;
-            }
-            Ok(None) => {}
-            Err(e) => {
-                eprintln!("Error checking last task run time: {}", e);
-                continue;
-            }
+async fn create_markets(app_state: Arc<AppState>) -> JobResult {
+    match ingest_markets(&app_state).await {
+        Ok(report) => {
+            let skipped: usize = report.skipped.values().sum();
+            println!("Task: {} markets fetched, {} inserted, {} updated, {} skipped", report.fetched, report.inserted, report.updated, skipped);
+            Ok(json!(report))
         }
-
-        let _ = sqlx::query!(
-            "INSERT INTO kv (kv_key, kv_ts)
-            VALUES ($1, NOW() + INTERVAL '1 hour')
-            ON CONFLICT (kv_key) DO UPDATE
-            SET kv_ts = NOW() + INTERVAL '1 hour'",
-            "predictions_task")
-            .execute(&*app_state.pool)
-            .await;
+        Err(e) => Err(format!("Error ingesting markets: {}", e)),
     }
 }
 
-async fn create_markets(app_state: Arc<AppState>) -> () {
-    let mut fetched = 0;
-    let mut inserted = 0;
-    let mut next_cursor = "MzUwMDA=".to_string();
-    let mut markets: Vec<Value> = Vec::new();
-    let client = reqwest::Client::new();
-    let now = Utc::now();
-
-    let allowed_tags = vec![
-        "Crypto", "Memecoins", "Politics", "Geopolitics", "Foreign Policy",
-        "Breaking News", "Elon Musk", "Twitter", "Tech", "Business", "AI"
-    ];
-    let disallowed_tags = vec!["German Election", "Tweet Markets"];
-
-    for _ in 0..50 {
-        println!("Task: Fetching page with next_cursor {}", next_cursor);
-
-        let url = format!("https://clob.polymarket.com/markets?next_cursor={}", next_cursor);
-        let response = match client.get(&url).send().await {
-            Ok(resp) => resp,
-            Err(e) => {
-                eprintln!("Task: Error fetching data: {}", e);
-                return;
-            }
-        };
-
-        let json: Value = match response.json().await {
-            Ok(json) => json,
-            Err(e) => {
-                eprintln!("Task: Error parsing response: {}", e);
-                return;
-            }
-        };
-
-        if !json.get("data").is_some() {
-            break;
-        }
-
-        if let Some(data) = json.get("data").and_then(|v| v.as_array()) {
-            for item in data {
-                markets.push(item.clone());
-            }
-        }
-
-        match json.get("next_cursor").and_then(|v| v.as_str()) {
-            Some(cursor) if cursor != next_cursor => {
-                next_cursor = cursor.to_string();
-            }
-            _ => break,
+async fn create_events(app_state: Arc<AppState>) -> JobResult {
+    match ingest_events(&app_state).await {
+        Ok(report) => {
+            println!("Task: {} events fetched, {} stored, {} markets linked", report.fetched, report.stored, report.linked);
+            Ok(json!(report))
         }
+        Err(e) => Err(format!("Error ingesting events: {}", e)),
     }
+}
 
-    let markets: Vec<&Value> = markets.iter()
-        .filter(|market| market.get("end_date_iso").is_some())
-        .collect();
-
-    for market in markets {
-        let active = market.get("active").and_then(|v| v.as_bool()).unwrap_or(false);
-        let closed = market.get("closed").and_then(|v| v.as_bool()).unwrap_or(true);
-        let archived = market.get("archived").and_then(|v| v.as_bool()).unwrap_or(true);
-
-        if !active || closed || archived {
-            continue;
-        }
-
-        let condition_id = match market.get("condition_id").and_then(|v| v.as_str()) {
-            Some(id) => id,
-            None => continue,
-        };
-
-        let question = match market.get("question").and_then(|v| v.as_str()) {
-            Some(q) => q,
-            None => continue,
-        };
-
-        let description = market.get("description")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .split_whitespace()
-            .collect::<Vec<&str>>()
-            .join(" ");
-
-        let tags = match market.get("tags").and_then(|v| v.as_array()) {
-            Some(tags_arr) => tags_arr
-                .iter()
-                .filter_map(|t| t.as_str())
-                .collect::<Vec<&str>>(),
-            None => continue,
+async fn track_prices(app_state: Arc<AppState>) -> JobResult {
+    let outcomes = match sqlx::query!(
+        "SELECT mo.condition_id, mo.token_id FROM market_outcomes mo
+        WHERE EXISTS (
+            SELECT 1 FROM predictions p
+            WHERE p.condition_id = mo.condition_id AND p.end_date > NOW()
+        )")
+        .fetch_all(&*app_state.pool)
+        .await {
+            Ok(outcomes) => outcomes,
+            Err(e) => return Err(format!("Error fetching tracked markets: {}", e))
         };
 
-        if !tags.iter().any(|tag| allowed_tags.contains(tag)) {
-            continue;
-        }
+    let token_ids: Vec<String> = outcomes.iter()
+        .map(|outcome| outcome.token_id.clone())
+        .collect();
 
-        if tags.iter().any(|tag| disallowed_tags.contains(tag)) {
-            continue;
-        }
+    let prices = match app_state.polymarket.prices(&token_ids).await {
+        Ok(prices) => prices,
+        Err(e) => return Err(format!("Error fetching market prices: {}", e))
+    };
 
-        let tags = format!("[{}]", tags.join(", "));
-
-        let mut yes_token_id = None;
-        let mut no_token_id = None;
-
-        if let Some(tokens) = market.get("tokens").and_then(|v| v.as_array()) {
-            for token in tokens {
-                if let Some(outcome) = token.get("outcome").and_then(|v| v.as_str()) {
-                    if let Some(token_id) = token.get("token_id").and_then(|v| v.as_str()) {
-                        match outcome.to_lowercase().as_str() {
-                            "yes" | "up" => yes_token_id = Some(token_id.to_string()),
-                            "no" | "down" => no_token_id = Some(token_id.to_string()),
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        }
+    let mut stored = 0;
 
-        let end_date = match market.get("end_date_iso").and_then(|v| v.as_str()) {
-            Some(date) => date,
+    for outcome in &outcomes {
+        let price = match prices.get(&outcome.token_id).and_then(|quote| quote.buy) {
+            Some(price) => price,
             None => continue,
         };
 
-        if let Ok(date) = DateTime::parse_from_rfc3339(end_date) {
-            if date < now {
-                continue;
-            }
-        } else {
-            continue;
-        }
-
-        fetched += 1;
-
-        let result = sqlx::query(
-            "INSERT INTO markets (condition_id, question, description, tags, yes_token_id, no_token_id, end_date, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7::timestamp, NOW())")
-            .bind(&condition_id)
-            .bind(&question)
-            .bind(&description)
-            .bind(&tags)
-            .bind(&yes_token_id)
-            .bind(&no_token_id)
-            .bind(&end_date)
+        let result = sqlx::query!(
+            "INSERT INTO prices (condition_id, token_id, price) VALUES ($1, $2, $3)",
+            outcome.condition_id,
+            outcome.token_id,
+            price)
             .execute(&*app_state.pool)
             .await;
 
         match result {
-            Ok(_) => {
-                inserted += 1;
-            },
-            Err(e) => {
-                if let Some(db_error) = e.as_database_error() {
-                    if db_error.is_unique_violation() {
-                        // Already exists, just continue
-                    }
-                }
-            }
+            Ok(_) => stored += 1,
+            Err(e) => eprintln!("Task: Error storing price for market {}: {}", outcome.condition_id, e),
         }
     }
 
-    println!("Task: {} markets fetched, {} markets inserted", fetched, inserted);
+    println!("Task: Stored {} market prices", stored);
+
+    Ok(json!({"tokens": outcomes.len(), "stored": stored}))
 }
 
-async fn track_predictions(app_state: Arc<AppState>) -> () {
+async fn track_predictions(app_state: Arc<AppState>) -> JobResult {
     let predictions = match sqlx::query!(
         "SELECT p.* FROM predictions p
         WHERE p.end_date > NOW()")
         .fetch_all(&*app_state.pool)
         .await {
             Ok(predictions) => predictions,
-            Err(e) => {
-                eprintln!("Task: Error fetching predictions: {}", e);
-                return;
-            }
+            Err(e) => return Err(format!("Error fetching predictions: {}", e))
         };
 
-    for prediction in predictions {
-        let client = reqwest::Client::new();
-
-        let weighted_url = format!("{}/api/v2/validator/events/{}/predictions", app_state.config.api_url, prediction.prediction_id);
-        let weighted_response = match client
-            .get(&weighted_url)
-            .header("X-API-Key", app_state.config.api_key.clone())
-            .send()
-            .await {
-                Ok(resp) => resp,
-                Err(e) => {
-                    eprintln!("Task: API request failed for weighted prediction for {}: {}", prediction.prediction_id, e);
-                    continue;
-                }
-            };
-
-        if !weighted_response.status().is_success() {
-            let error = match weighted_response.text().await {
-                Ok(text) => format!("Task: API request failed for weighted prediction for {}: {}", prediction.prediction_id, text),
-                Err(_) => "Task: API request failed for weighted prediction".to_string(),
-            };
-            eprintln!("{}", error);
-            continue;
-        }
+    let aggregator = match build_aggregator(&app_state.pool, &app_state.config.aggregator).await {
+        Some(aggregator) => aggregator,
+        None => return Err(format!("Unknown aggregator {}", app_state.config.aggregator))
+    };
 
-        let weighted_json = match weighted_response.json::<Value>().await {
-            Ok(json) => json,
-            Err(e) => {
-                eprintln!("Task: Error parsing weighted predictions response for {}: {}", prediction.prediction_id, e);
-                continue;
-            }
-        };
+    let mut stored = 0;
+    let mut failed = 0;
 
-        let outcomes = match weighted_json.get("predictions").and_then(|v| v.as_array()) {
-            Some(preds) => preds,
-            None => {
-                eprintln!("Task: No predictions available yet for {}", prediction.prediction_id);
-                continue;
+    for prediction in &predictions {
+        match fetch_outcome(&app_state, aggregator.as_ref(), &prediction.prediction_id, &prediction.condition_id).await {
+            Ok(result) => {
+                stored += 1;
+                println!("Task: Stored new outcome for prediction {} (weighted: {}, community: {}, aggregator: {})", prediction.prediction_id, result.weighted, result.community, result.aggregator);
             }
-        };
-
-        if outcomes.is_empty() {
-            eprintln!("Task: No predictions available yet for {}", prediction.prediction_id);
-            continue;
-        }
-
-        let mut sum = 0.0;
-        let mut count = 0;
-
-        for outcome in outcomes {
-            if let Some(pred) = outcome.get("predictedOutcome").and_then(|v| v.as_str()) {
-                if let Ok(value) = pred.parse::<f64>() {
-                    sum += value;
-                    count += 1;
-                }
+            Err(e) => {
+                failed += 1;
+                eprintln!("Task: Error fetching outcome for prediction {}: {}", prediction.prediction_id, e);
             }
         }
+    }
 
-        if count == 0 {
-            eprintln!("Task: No predictions available yet {}", prediction.prediction_id);
-            continue;
-        }
+    Ok(json!({"predictions": predictions.len(), "stored": stored, "failed": failed}))
+}
+
+async fn resolve_markets(app_state: Arc<AppState>) -> JobResult {
+    let markets = match sqlx::query!(
+        "SELECT DISTINCT m.condition_id, m.yes_token_id FROM markets m
+        JOIN predictions p ON m.condition_id = p.condition_id
+        WHERE m.end_date <= NOW() AND NOT m.resolved")
+        .fetch_all(&*app_state.pool)
+        .await {
+            Ok(markets) => markets,
+            Err(e) => return Err(format!("Error fetching unresolved markets: {}", e))
+        };
 
-        let weighted = (sum / count as f64 * 10000.0).round() / 10000.0;
+    let mut resolved = 0;
 
-        let community_url = format!("{}/api/v2/validator/events/{}/community_prediction", app_state.config.api_url, prediction.prediction_id);
-        let community_response = match client
-            .get(&community_url)
-            .header("X-API-Key", app_state.config.api_key.clone())
-            .send()
-            .await {
-            Ok(resp) => resp,
+    for market in &markets {
+        let details = match app_state.polymarket.market(&market.condition_id).await {
+            Ok(details) => details,
             Err(e) => {
-                eprintln!("Task: API request failed for community prediction for {}: {}", prediction.prediction_id, e);
+                eprintln!("Task: Error fetching market {}: {}", market.condition_id, e);
                 continue;
             }
         };
 
-        if !community_response.status().is_success() {
-            let error = match community_response.text().await {
-                Ok(text) => format!("Task: API request failed for community prediction for {}: {}", prediction.prediction_id, text),
-                Err(_) => "Task: API request failed for community prediction".to_string(),
-            };
-            eprintln!("{}", error);
+        if !details.closed.unwrap_or(true) {
             continue;
         }
 
-        let community = match community_response.json::<Value>().await {
-            Ok(json) => match json.get("community_prediction").and_then(|v| v.as_f64()) {
-                Some(value) => (value * 10000.0).round() / 10000.0,
-                None => {
-                    eprintln!("Task: No community prediction available for {}", prediction.prediction_id);
-                    continue;
-                }
-            },
+        let winner = match details.tokens.iter().find(|token| token.winner) {
+            Some(winner) => winner,
+            None => continue,
+        };
+
+        let final_price = details.tokens.iter()
+            .find(|token| Some(token.token_id.as_str()) == market.yes_token_id.as_deref())
+            .and_then(|token| token.price);
+
+        // The CLOB only reports that a market closed, not when; Gamma has the timestamp.
+        let resolved_at = match app_state.gamma.market(&market.condition_id).await {
+            Ok(gamma) => gamma.and_then(|gamma| gamma.resolved_at()).map(|date| date.naive_utc()),
             Err(e) => {
-                eprintln!("Task: Error parsing community prediction response for {}: {}", prediction.prediction_id, e);
+                eprintln!("Task: Error fetching close time for market {}: {}", market.condition_id, e);
                 continue;
             }
         };
 
         let result = sqlx::query!(
-            "INSERT INTO outcomes (prediction_id, weighted, community, raw) VALUES ($1, $2, $3, $4)",
-            prediction.prediction_id,
-            weighted,
-            community,
-            weighted_json)
+            "UPDATE markets
+            SET resolved = TRUE, outcome = $2, winning_token_id = $3, final_price = $4, resolved_at = COALESCE($5::timestamp, NOW())
+            WHERE condition_id = $1",
+            market.condition_id,
+            winner.outcome,
+            winner.token_id,
+            final_price,
+            resolved_at)
             .execute(&*app_state.pool)
             .await;
 
+        let result = match result {
+            Ok(_) => sqlx::query!(
+                "UPDATE market_outcomes SET winner = (token_id = $2) WHERE condition_id = $1",
+                market.condition_id,
+                winner.token_id)
+                .execute(&*app_state.pool)
+                .await,
+            Err(e) => Err(e),
+        };
+
         match result {
-            Ok(_) => println!("Task: Stored new outcome for prediction {} (weighted: {}, community: {})", prediction.prediction_id, weighted, community),
-            Err(e) => eprintln!("Task: Error storing outcome for prediction {}: {}", prediction.prediction_id, e),
+            Ok(_) => {
+                resolved += 1;
+                println!("Task: Resolved market {} with outcome {}", market.condition_id, winner.outcome);
+            }
+            Err(e) => eprintln!("Task: Error storing resolution for market {}: {}", market.condition_id, e),
         }
     }
+
+    println!("Task: {} markets resolved", resolved);
+
+    Ok(json!({"markets": markets.len(), "resolved": resolved}))
 }
 
-async fn create_predictions(app_state: Arc<AppState>) -> () {
+async fn create_predictions(app_state: Arc<AppState>) -> JobResult {
     let markets = match sqlx::query_as!(
         Market,
         "SELECT m.*
@@ -353,74 +232,115 @@ async fn create_predictions(app_state: Arc<AppState>) -> () {
         .fetch_all(&*app_state.pool)
         .await {
             Ok(market) => market,
+            Err(e) => return Err(format!("Error fetching markets: {}", e))
+        };
+
+    for market in &markets {
+        let targets = match prediction_targets(&app_state.pool, market, None).await {
+            Ok(targets) => targets,
             Err(e) => {
-                eprintln!("Task: Error fetching markets: {}", e);
-                return;
+                eprintln!("Task: Error fetching outcomes for {}: {}", market.condition_id, e);
+                continue;
             }
         };
 
-    for market in markets {
-        let cutoff = market.end_date.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true);
-
-        let request_body = serde_json::json!({
-            "title": market.question,
-            "description": market.description,
-            "cutoff": cutoff,
-        });
-
-        let client = reqwest::Client::new();
-        let api_url = format!("{}/api/v2/events", app_state.config.api_url);
-
-        let response = match client
-            .post(api_url)
-            .header("X-API-Key", app_state.config.api_key.clone())
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await {
-                Ok(resp) => resp,
-                Err(e) => {
-                    eprintln!("Task: Error making API request: {}", e);
-                    continue;
+        let mut created = 0;
+        for outcome in &targets {
+            match create_outcome_prediction(&app_state, market, outcome).await {
+                Ok(prediction) => {
+                    created += 1;
+                    println!("Task: Created prediction {} for market \"{}\" ({})", prediction.prediction_id, market.question, outcome.outcome);
                 }
-            };
-
-        if !response.status().is_success() {
-            let error = match response.text().await {
-                Ok(text) => format!("Task: API request failed for prediction: {}\nRequest: {}\nResponse: {}", market.condition_id, request_body, text),
-                Err(_) => format!("Task: API request failed for prediction: {}\nRequest: {}\nError: Unable to read response", market.question, request_body),
-            };
-            eprintln!("{}", error);
-            continue;
+                Err(e) => eprintln!("Task: Error creating prediction for {} ({}): {}", market.condition_id, outcome.outcome, e),
+            }
         }
 
-        let prediction_response = match response.json::<Value>().await {
-            Ok(json) => json,
-            Err(e) => {
-                eprintln!("Task: Failed to parse API response for prediction: {}\nRequest: {}\nError: {}", market.condition_id, request_body, e);
-                continue;
-            }
+        if created > 0 {
+            return Ok(json!({"condition_id": market.condition_id, "created": created}));
+        }
+    }
+
+    Ok(json!({"created": 0}))
+}
+
+
+/// Settles ledger transactions left pending, e.g. withdrawals whose confirmation timed out. Anything the chain
+/// still has not seen once its blockhash is long expired is marked failed.
+async fn reconcile_transactions(app_state: Arc<AppState>) -> JobResult {
+    let pending = match sqlx::query!(
+        "SELECT id, signature, created_at < NOW() - INTERVAL '15 minutes' AS \"expired!\"
+        FROM ledger_transactions
+        WHERE status = 'pending' AND created_at < NOW() - INTERVAL '1 minute'
+        ORDER BY created_at ASC
+        LIMIT 1000")
+        .fetch_all(&*app_state.pool)
+        .await {
+            Ok(pending) => pending,
+            Err(e) => return Err(format!("Error fetching pending transactions: {}", e))
         };
 
-        let prediction_id = match prediction_response.get("event_id").and_then(|v| v.as_str()) {
-            Some(id) => id.to_string(),
-            None => {
-                eprintln!("Task: No event_id in API response for prediction: {}\nRequest: {}\nResponse: {}", market.condition_id, request_body, prediction_response);
-                continue;
-            }
+    let signed: Vec<(i64, Signature)> = pending.iter()
+        .filter_map(|transaction| Some((transaction.id, transaction.signature.as_deref()?.parse().ok()?)))
+        .collect();
+
+    let signatures: Vec<Signature> = signed.iter().map(|(_, signature)| *signature).collect();
+    let statuses = match signature_statuses(&app_state.solana, &signatures).await {
+        Ok(statuses) => statuses,
+        Err(e) => return Err(format!("Error fetching signature statuses: {}", e))
+    };
+
+    let statuses: HashMap<i64, Result<(), String>> = signed.iter()
+        .zip(statuses)
+        .filter_map(|((id, _), status)| Some((*id, status?)))
+        .collect();
+
+    let (mut confirmed, mut failed) = (0, 0);
+
+    for transaction in &pending {
+        let (status, error) = match settlement(statuses.get(&transaction.id), transaction.expired, transaction.signature.is_some()) {
+            Some(settlement) => settlement,
+            None => continue,
         };
 
-        let end_date = market.end_date;
+        match ledger::settle(&app_state.pool, transaction.id, status, None, error, None).await {
+            Ok(()) if error.is_none() => confirmed += 1,
+            Ok(()) => failed += 1,
+            Err(e) => eprintln!("Task: Error settling transaction {}: {}", transaction.id, e),
+        }
+    }
 
-        let _ = sqlx::query!(
-            "INSERT INTO predictions (prediction_id, condition_id, end_date, created_at) VALUES ($1, $2, $3, NOW())",
-            prediction_id,
-            market.condition_id,
-            end_date)
-            .execute(&*app_state.pool)
-            .await;
+    println!("Task: {} pending transactions, {} confirmed, {} failed", pending.len(), confirmed, failed);
+    Ok(json!({"pending": pending.len(), "confirmed": confirmed, "failed": failed}))
+}
 
-        println!("Task: Created prediction {} for market \"{}\"", prediction_id, market.question);
-        return;
+/// How a pending transaction settles given its signature status, or `None` to leave it pending. `status` comes from
+/// a lookup that searches the full transaction history, so a missing status on an expired row means it never landed.
+fn settlement(status: Option<&Result<(), String>>, expired: bool, signed: bool) -> Option<(ledger::Status, Option<&str>)> {
+    match status {
+        Some(Ok(())) => Some((ledger::Status::Confirmed, None)),
+        Some(Err(e)) => Some((ledger::Status::Failed, Some(e.as_str()))),
+        // Without a signature there is nothing to look up, so the transaction may well have landed.
+        None if expired && signed => Some((ledger::Status::Failed, Some("not confirmed before expiry"))),
+        None => None,
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_statuses_fail_only_once_expired() {
+        assert_eq!(settlement(None, false, true), None);
+        assert_eq!(settlement(None, true, false), None);
+        assert_eq!(settlement(None, true, true), Some((ledger::Status::Failed, Some("not confirmed before expiry"))));
+    }
+
+    #[test]
+    fn found_statuses_settle_even_when_expired() {
+        let failed = Err("InstructionError".to_string());
+
+        assert_eq!(settlement(Some(&Ok(())), true, true), Some((ledger::Status::Confirmed, None)));
+        assert_eq!(settlement(Some(&failed), true, true), Some((ledger::Status::Failed, Some("InstructionError"))));
+    }
+}
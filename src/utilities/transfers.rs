@@ -0,0 +1,265 @@
+use std::fmt;
+use std::str::FromStr;
+use serde_json::{json, Value};
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::Signer as _;
+use solana_sdk::transaction::VersionedTransaction;
+use spl_associated_token_account_client::address::get_associated_token_address_with_program_id;
+use spl_associated_token_account_client::instruction::create_associated_token_account_idempotent;
+
+use crate::models::Wallet;
+use crate::utilities::app_state::AppState;
+use crate::utilities::signer::{keypair, sign_transaction, wallet_signer, SignerError};
+
+pub const USDC_DECIMALS: u8 = 6;
+
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// Size of an SPL token account, which sets the rent needed to create one.
+const TOKEN_ACCOUNT_LEN: usize = 165;
+
+#[derive(Debug)]
+pub enum TransferError {
+    InvalidAddress(String),
+    InvalidAmount,
+    GasWallet(SignerError),
+    Signer(SignerError),
+    Rejected(String),
+    Rpc(Box<ClientError>),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferError::InvalidAddress(address) => write!(f, "invalid address {}", address),
+            TransferError::InvalidAmount => write!(f, "invalid amount"),
+            TransferError::GasWallet(e) => write!(f, "gas wallet: {}", e),
+            TransferError::Signer(e) => write!(f, "{}", e),
+            TransferError::Rejected(e) => write!(f, "transaction rejected: {}", e),
+            TransferError::Rpc(e) => write!(f, "RPC request failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TransferError {}
+
+/// Builds an SPL `transfer_checked` between the owners' associated token accounts, creating the destination
+/// account first if it does not exist yet. The fee payer covers fees and rent, so the owner never needs SOL.
+pub fn build_token_transfer(
+    fee_payer: &Pubkey,
+    owner: &Pubkey,
+    destination: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    blockhash: Hash,
+) -> Result<VersionedTransaction, TransferError> {
+    let token_program = spl_token::id();
+    let source_account = get_associated_token_address_with_program_id(owner, mint, &token_program);
+    let destination_account = get_associated_token_address_with_program_id(destination, mint, &token_program);
+
+    let instructions = vec![
+        create_associated_token_account_idempotent(fee_payer, destination, mint, &token_program),
+        spl_token::instruction::transfer_checked(
+            &token_program,
+            &source_account,
+            mint,
+            &destination_account,
+            owner,
+            &[],
+            amount,
+            decimals)
+            .map_err(|_| TransferError::InvalidAmount)?,
+    ];
+
+    let message = Message::new_with_blockhash(&instructions, Some(fee_payer), &blockhash);
+    let signatures = vec![Signature::default(); message.header.num_required_signatures as usize];
+
+    Ok(VersionedTransaction { signatures, message: VersionedMessage::Legacy(message) })
+}
+
+/// Builds and signs a transfer of `amount` USDC base units from the wallet to `destination`. Both the gas wallet
+/// and the wallet's signer sign in-process; nothing is sent until `submit_transaction`.
+pub async fn prepare_usdc_transfer(state: &AppState, wallet: &Wallet, destination: &str, amount: u64) -> Result<VersionedTransaction, TransferError> {
+    let owner = Pubkey::from_str(&wallet.address).map_err(|_| TransferError::InvalidAddress(wallet.address.clone()))?;
+    let destination = Pubkey::from_str(destination).map_err(|_| TransferError::InvalidAddress(destination.to_string()))?;
+    let mint = Pubkey::from_str(&state.config.solana_usdc_mint)
+        .map_err(|_| TransferError::InvalidAddress(state.config.solana_usdc_mint.clone()))?;
+    let gas = keypair(&state.config.solana_gas_secret, &state.config.solana_gas_address).map_err(TransferError::GasWallet)?;
+
+    let blockhash = state.solana.get_latest_blockhash().await.map_err(|e| TransferError::Rpc(Box::new(e)))?;
+    let mut transaction = build_token_transfer(&gas.pubkey(), &owner, &destination, &mint, amount, USDC_DECIMALS, blockhash)?;
+
+    // The fee payer is always the first signer.
+    transaction.signatures[0] = gas.sign_message(&transaction.message.serialize());
+    sign_transaction(&*wallet_signer(state, wallet), wallet, &mut transaction).await
+        .map_err(TransferError::Signer)?;
+
+    Ok(transaction)
+}
+
+/// Submits a signed transaction and waits for confirmation. `Rejected` means it failed simulation or on chain;
+/// any other error leaves its fate unknown.
+pub async fn submit_transaction(state: &AppState, transaction: &VersionedTransaction) -> Result<Signature, TransferError> {
+    state.solana.send_and_confirm_transaction(transaction).await.map_err(|e| match e.get_transaction_error() {
+        Some(error) => TransferError::Rejected(error.to_string()),
+        None => TransferError::Rpc(Box::new(e)),
+    })
+}
+
+/// The base network fee of a transaction, in lamports.
+pub fn network_fee(transaction: &VersionedTransaction) -> u64 {
+    LAMPORTS_PER_SIGNATURE * transaction.message.header().num_required_signatures as u64
+}
+
+/// Rent, in lamports, the fee payer deposits into `destination`'s USDC token account when the transfer has to create
+/// it. Zero if the account already exists.
+pub async fn destination_account_rent(state: &AppState, destination: &str) -> Result<u64, TransferError> {
+    let destination = Pubkey::from_str(destination).map_err(|_| TransferError::InvalidAddress(destination.to_string()))?;
+    let mint = Pubkey::from_str(&state.config.solana_usdc_mint)
+        .map_err(|_| TransferError::InvalidAddress(state.config.solana_usdc_mint.clone()))?;
+    let account = get_associated_token_address_with_program_id(&destination, &mint, &spl_token::id());
+
+    let existing = state.solana.get_account_with_commitment(&account, CommitmentConfig::confirmed()).await
+        .map_err(|e| TransferError::Rpc(Box::new(e)))?;
+    if existing.value.is_some() {
+        return Ok(0);
+    }
+
+    state.solana.get_minimum_balance_for_rent_exemption(TOKEN_ACCOUNT_LEN).await.map_err(|e| TransferError::Rpc(Box::new(e)))
+}
+
+/// The confirmed status of each signature: `Some(Ok)` landed, `Some(Err)` failed on chain, `None` not (yet) seen.
+pub async fn signature_statuses(solana: &RpcClient, signatures: &[Signature]) -> Result<Vec<Option<Result<(), String>>>, TransferError> {
+    let mut statuses = Vec::with_capacity(signatures.len());
+
+    // The RPC accepts at most 256 signatures per request. Without the history lookup only recent signatures are
+    // found, so an older transaction that landed would look missing.
+    for chunk in signatures.chunks(256) {
+        let response = solana.get_signature_statuses_with_history(chunk).await.map_err(|e| TransferError::Rpc(Box::new(e)))?;
+        statuses.extend(response.value.into_iter().map(|status| {
+            let status = status.filter(|status| status.satisfies_commitment(CommitmentConfig::confirmed()) || status.err.is_some())?;
+            Some(match status.err {
+                Some(e) => Err(e.to_string()),
+                None => Ok(()),
+            })
+        }));
+    }
+
+    Ok(statuses)
+}
+
+/// How many base units of `mint` a confirmed transaction credited to `owner`'s token accounts (zero if it only
+/// debited them). `None` if the transaction is not confirmed yet or failed.
+pub async fn token_credit(state: &AppState, signature: &Signature, owner: &str, mint: &str) -> Result<Option<u64>, TransferError> {
+    let transaction = state.solana.send::<Value>(RpcRequest::GetTransaction, json!([
+        signature.to_string(),
+        {"encoding": "json", "commitment": "confirmed", "maxSupportedTransactionVersion": 0}
+    ])).await.map_err(|e| TransferError::Rpc(Box::new(e)))?;
+
+    let meta = match transaction.get("meta") {
+        Some(meta) if meta.get("err").is_some_and(Value::is_null) => meta,
+        _ => return Ok(None),
+    };
+
+    let balance = |key: &str| -> i128 {
+        meta.get(key).and_then(|balances| balances.as_array()).into_iter().flatten()
+            .filter(|balance| balance.get("owner").and_then(|v| v.as_str()) == Some(owner)
+                && balance.get("mint").and_then(|v| v.as_str()) == Some(mint))
+            .filter_map(|balance| balance.get("uiTokenAmount")?.get("amount")?.as_str()?.parse::<i128>().ok())
+            .sum()
+    };
+
+    let credit = balance("postTokenBalances") - balance("preTokenBalances");
+    Ok(Some(u64::try_from(credit).unwrap_or(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use spl_token::instruction::TokenInstruction;
+
+    #[test]
+    fn builds_a_checked_transfer_paid_by_the_fee_payer() {
+        let (fee_payer, owner, destination, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let transaction = build_token_transfer(&fee_payer, &owner, &destination, &mint, 2_500_000, USDC_DECIMALS, Hash::new_unique()).unwrap();
+        let message = match &transaction.message {
+            VersionedMessage::Legacy(message) => message,
+            _ => panic!("expected a legacy message"),
+        };
+
+        assert_eq!(message.account_keys[0], fee_payer);
+        assert_eq!(message.header.num_required_signatures, 2);
+        assert_eq!(transaction.signatures, vec![Signature::default(); 2]);
+        assert_eq!(network_fee(&transaction), 10_000);
+
+        let programs: Vec<Pubkey> = message.instructions.iter()
+            .map(|instruction| message.account_keys[instruction.program_id_index as usize])
+            .collect();
+        assert_eq!(programs, vec![spl_associated_token_account_client::program::id(), spl_token::id()]);
+
+        match TokenInstruction::unpack(&message.instructions[1].data).unwrap() {
+            TokenInstruction::TransferChecked { amount, decimals } => assert_eq!((amount, decimals), (2_500_000, USDC_DECIMALS)),
+            _ => panic!("expected transfer_checked"),
+        }
+
+        let accounts: Vec<Pubkey> = message.instructions[1].accounts.iter()
+            .map(|index| message.account_keys[*index as usize])
+            .collect();
+        let token_program = spl_token::id();
+        assert_eq!(accounts, vec![
+            get_associated_token_address_with_program_id(&owner, &mint, &token_program),
+            mint,
+            get_associated_token_address_with_program_id(&destination, &mint, &token_program),
+            owner,
+        ]);
+    }
+
+    #[test]
+    fn owner_paying_its_own_fees_signs_once() {
+        let (owner, destination, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let transaction = build_token_transfer(&owner, &owner, &destination, &mint, 1, USDC_DECIMALS, Hash::new_unique()).unwrap();
+
+        assert_eq!(transaction.message.header().num_required_signatures, 1);
+        assert_eq!(transaction.signatures.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn statuses_are_looked_up_in_the_full_history() {
+        let (landed, missing) = (Signature::new_unique(), Signature::new_unique());
+
+        // Only the history lookup knows about the landed transaction, as for one that left the recent status cache.
+        let router = Router::new().route("/", post(move |Json(request): Json<Value>| async move {
+            assert_eq!(request["method"], "getSignatureStatuses");
+            assert_eq!(request["params"][0], json!([landed.to_string(), missing.to_string()]));
+            let history = request["params"][1]["searchTransactionHistory"] == true;
+
+            let status = json!({"slot": 1, "confirmations": null, "err": null, "status": {"Ok": null}, "confirmationStatus": "finalized"});
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": {"context": {"slot": 1}, "value": [if history { status } else { Value::Null }, null]}
+            }))
+        }));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let solana = RpcClient::new(format!("http://{}/", address));
+        let statuses = signature_statuses(&solana, &[landed, missing]).await.unwrap();
+
+        assert_eq!(statuses, vec![Some(Ok(())), None]);
+    }
+}